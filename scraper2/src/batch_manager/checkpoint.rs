@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+use crate::batch_manager::job::ScrapeJob;
+use crate::batch_manager::request::models::SearchResult;
+use crate::batch_manager::types::{AttributeBucketCombinations, ComponentCount};
+use crate::config::constants::CHECKPOINT_FILE_SUFFIX;
+use crate::data_manager::DataManager;
+
+/// The settings of a job that decide which combinations and pages it fetches.
+///
+/// A checkpoint can only be resumed by a job with the same settings.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct JobSettings {
+    attribute_shortnames: Vec<String>,
+    split_fallback_shortnames: Vec<String>,
+    sort_sweep_key: Option<String>,
+}
+
+impl From<&ScrapeJob> for JobSettings {
+    fn from(job: &ScrapeJob) -> Self {
+        Self {
+            attribute_shortnames: job.attribute_shortnames.clone(),
+            split_fallback_shortnames: job.split_fallback_shortnames.clone(),
+            sort_sweep_key: job.sort_sweep_key.clone(),
+        }
+    }
+}
+
+/// One line of the checkpoint file.
+///
+/// The first line holds the job's settings and the bucket combinations computed by the counting
+/// phase; every following line holds a page of components that was fetched successfully.
+///
+/// Example:
+/// ```json
+/// {"settings":{"attribute_shortnames":[...],...},"combinations":{"combinations":[...]}}
+/// {"page":{"attribute_bucket_combination":{...},"start":0,"end":100,"sort":null},"components":[...]}
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CheckpointEntry {
+    Combinations {
+        settings: JobSettings,
+        combinations: AttributeBucketCombinations,
+    },
+    Page {
+        page: ComponentCount,
+        components: Vec<SearchResult>,
+    },
+}
+
+/// The state recovered from a checkpoint file.
+pub(crate) struct ResumedCheckpoint {
+    pub(crate) checkpoint: Checkpoint,
+    pub(crate) attribute_bucket_combinations: AttributeBucketCombinations,
+    pub(crate) completed_pages: Vec<(ComponentCount, Vec<SearchResult>)>,
+}
+
+/// An append-only record of a job's progress, stored next to its output in the save directory.
+///
+/// Appending one line per completed page keeps writes cheap and means a crash can only ever
+/// lose the line being written, which is skipped on resume.
+pub(crate) struct Checkpoint {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// Starts a new checkpoint for the job, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `save_dir` - The directory the job's output is saved to.
+    /// * `job` - The job being checkpointed.
+    /// * `attribute_bucket_combinations` - The combinations computed by the counting phase.
+    pub(crate) async fn create(
+        save_dir: &Path,
+        job: &ScrapeJob,
+        attribute_bucket_combinations: &AttributeBucketCombinations,
+    ) -> Result<Self> {
+        fs::create_dir_all(save_dir).await?;
+        let path = Self::path_for(save_dir, job);
+        let file = File::create(&path).await?;
+        let checkpoint = Self {
+            path,
+            file: Mutex::new(file),
+        };
+        checkpoint
+            .append(&[CheckpointEntry::Combinations {
+                settings: JobSettings::from(job),
+                combinations: AttributeBucketCombinations {
+                    combinations: attribute_bucket_combinations.combinations.clone(),
+                },
+            }])
+            .await?;
+        Ok(checkpoint)
+    }
+
+    /// Loads the job's checkpoint, if there is one, and reopens it for appending.
+    ///
+    /// # Returns
+    /// `None` when the job has no checkpoint in the save directory.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint cannot be read, or was written by a job with other split
+    /// attributes, split fallbacks or sort sweep, whose combinations and pages do not apply.
+    pub(crate) async fn resume(
+        save_dir: &Path,
+        job: &ScrapeJob,
+    ) -> Result<Option<ResumedCheckpoint>> {
+        let path = Self::path_for(save_dir, job);
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let mut lines = content.lines();
+        let (settings, attribute_bucket_combinations) = match lines.next().map(serde_json::from_str)
+        {
+            Some(Ok(CheckpointEntry::Combinations {
+                settings,
+                combinations,
+            })) => (settings, combinations),
+            _ => {
+                return Err(anyhow!(
+                    "Checkpoint {} has no job settings and combinations",
+                    path.display()
+                ))
+            }
+        };
+        let job_settings = JobSettings::from(job);
+        if settings != job_settings {
+            bail!(
+                "Checkpoint {} was written with {:?}, but {} now uses {:?}; run without --resume \
+                 to start over",
+                path.display(),
+                settings,
+                job.category_name,
+                job_settings
+            );
+        }
+
+        // A page can appear twice if the run stopped between appending and returning; the
+        // last line may be cut short by a crash.
+        let mut seen_pages = HashSet::new();
+        let mut completed_pages = Vec::new();
+        for line in lines {
+            if let Ok(CheckpointEntry::Page { page, components }) = serde_json::from_str(line) {
+                if seen_pages.insert(page.page_key()) {
+                    completed_pages.push((page, components));
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new().append(true).open(&path).await?;
+        if !content.ends_with('\n') {
+            // Terminate the cut-short line so the next entry starts on a line of its own.
+            file.write_all(b"\n").await?;
+        }
+        Ok(Some(ResumedCheckpoint {
+            checkpoint: Self {
+                path,
+                file: Mutex::new(file),
+            },
+            attribute_bucket_combinations,
+            completed_pages,
+        }))
+    }
+
+    /// Records pages of components that were fetched successfully.
+    pub(crate) async fn append_pages(
+        &self,
+        pages: Vec<(ComponentCount, Vec<SearchResult>)>,
+    ) -> Result<()> {
+        let entries: Vec<_> = pages
+            .into_iter()
+            .map(|(page, components)| CheckpointEntry::Page { page, components })
+            .collect();
+        self.append(&entries).await
+    }
+
+    /// Deletes the checkpoint once the job's output has been saved.
+    pub(crate) async fn remove(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path).await?;
+        Ok(())
+    }
+
+    async fn append(&self, entries: &[CheckpointEntry]) -> Result<()> {
+        let mut buffer = String::new();
+        for entry in entries {
+            buffer.push_str(&serde_json::to_string(entry)?);
+            buffer.push('\n');
+        }
+        let mut file = self.file.lock().await;
+        file.write_all(buffer.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// The checkpoint of a job is named after its category and split attributes, so jobs that
+    /// split the same category differently keep separate checkpoints.
+    fn path_for(save_dir: &Path, job: &ScrapeJob) -> PathBuf {
+        save_dir.join(format!(
+            "{}_{}_{}.ndjson",
+            DataManager::sanitize_filename(&job.category_name),
+            DataManager::sanitize_filename(&job.attribute_shortnames.join("_")),
+            CHECKPOINT_FILE_SUFFIX
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Checkpoint;
+    use crate::batch_manager::job::ScrapeJob;
+    use crate::batch_manager::types::AttributeBucketCombinations;
+
+    fn job(attribute_names: &[&str], split_fallback: &[&str]) -> ScrapeJob {
+        let attribute_names: Vec<_> = attribute_names
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let split_fallback: Vec<_> = split_fallback.iter().map(|name| name.to_string()).collect();
+        ScrapeJob::new("Ceramic Capacitors", &attribute_names, &split_fallback).unwrap()
+    }
+
+    async fn create(save_dir: &Path, job: &ScrapeJob) {
+        let combinations = AttributeBucketCombinations {
+            combinations: Vec::new(),
+        };
+        Checkpoint::create(save_dir, job, &combinations)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_to_resume_with_other_settings() {
+        let save_dir = tempfile::tempdir().unwrap();
+        let job = job(&["Capacitance"], &["case_package"]);
+        create(save_dir.path(), &job).await;
+
+        assert!(Checkpoint::resume(save_dir.path(), &job)
+            .await
+            .unwrap()
+            .is_some());
+        let other_fallback = ScrapeJob {
+            split_fallback_shortnames: Vec::new(),
+            ..job.clone()
+        };
+        assert!(Checkpoint::resume(save_dir.path(), &other_fallback)
+            .await
+            .is_err());
+        let sort_sweep = ScrapeJob {
+            sort_sweep_key: Some("mpn".to_string()),
+            ..job
+        };
+        assert!(Checkpoint::resume(save_dir.path(), &sort_sweep)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_a_checkpoint_per_split() {
+        let save_dir = tempfile::tempdir().unwrap();
+        let by_capacitance = job(&["Capacitance"], &[]);
+        let by_voltage = job(&["Capacitance", "Voltage Rating"], &[]);
+        create(save_dir.path(), &by_capacitance).await;
+        create(save_dir.path(), &by_voltage).await;
+
+        for job in [&by_capacitance, &by_voltage] {
+            assert!(Checkpoint::resume(save_dir.path(), job)
+                .await
+                .unwrap()
+                .is_some());
+        }
+    }
+}
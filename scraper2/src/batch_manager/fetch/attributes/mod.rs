@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use log::debug;
 use tokio::sync::RwLock;
 
 use crate::{
     batch_manager::{
         fetch::tasks::TaskType,
+        job::ScrapeJob,
         request::{
             request_sender::{RequestSender, RequestType},
             response_handler::ResponseHandler,
@@ -13,11 +15,13 @@ use crate::{
         types::AttributeBuckets,
     },
     cli::Arguments,
-    config::prompts::print_task_error_message,
+    config::{constants::MAX_TASK_FAILURES, prompts::print_task_error_message},
+    error::ScraperError,
 };
 
 pub(crate) struct AttributeScraper {
     args: Arc<RwLock<Arguments>>,
+    job: Arc<ScrapeJob>,
     request_sender: Arc<RequestSender>,
     response_handler: Arc<ResponseHandler>,
 }
@@ -25,40 +29,62 @@ pub(crate) struct AttributeScraper {
 impl AttributeScraper {
     pub(crate) fn new(
         args: Arc<RwLock<Arguments>>,
+        job: Arc<ScrapeJob>,
         request_sender: Arc<RequestSender>,
         response_handler: Arc<ResponseHandler>,
     ) -> Self {
         Self {
             args,
+            job,
             request_sender,
             response_handler,
         }
     }
 
+    /// Fetches the buckets of the job's attributes.
+    ///
+    /// A PerimeterX block asks the user for a new PX key and tries again; other retryable failures
+    /// are retried up to `MAX_TASK_FAILURES` times. Any other error is returned straight away.
     pub(crate) async fn process(
         &self,
         attribute_bucket_display_values: &[String],
     ) -> Result<AttributeBuckets> {
+        let mut failures = 0;
         loop {
             let args_clone = self.args.read().await;
             let attribute_bucket_response = match self
                 .request_sender
                 .clone()
-                .send_request(&args_clone, RequestType::Attributes)
+                .send_request(
+                    &args_clone,
+                    &self.job,
+                    RequestType::Attributes,
+                    &self.response_handler,
+                )
                 .await
-                .map_err(anyhow::Error::new)
             {
                 Ok(json_response) => json_response,
-                Err(_) => {
+                Err(ScraperError::Blocked) => {
                     print_task_error_message(&TaskType::AttributeScraper, 1);
 
                     // Explicitly drop the read lock before acquiring a write lock, otherwise a
                     // deadlock will occur.
                     drop(args_clone);
 
-                    self.args.write().await.prompt_user_for_new_px_key();
+                    self.args.write().await.prompt_user_for_new_px_key()?;
+                    continue;
+                }
+                Err(error) if error.is_retryable() && failures + 1 < MAX_TASK_FAILURES => {
+                    failures += 1;
+                    debug!("Retrying attribute request after error: {}", error);
                     continue;
                 }
+                Err(error) => {
+                    return Err(anyhow::Error::new(error).context(format!(
+                        "Failed to fetch the attributes of {}",
+                        self.job.category_name
+                    )))
+                }
             };
 
             return self
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use crate::batch_manager::request::models::{Part, PartKey, SearchResult};
+use crate::error::ScraperError;
+
+/// Removes parts that were fetched by more than one page.
+///
+/// Bucket combinations overlap when a part lists several values for an attribute, when float
+/// filters match neighbouring buckets, and when a sort sweep reads a combination from both ends.
+/// The first copy of a part is kept where it was found and every later copy is merged into it:
+/// specs the first copy lacks are added, and when two copies disagree on a spec the display value
+/// that sorts first wins, so the result does not depend on the order pages finished in.
+///
+/// # Arguments
+/// * `results` - The components of every page, in page order.
+///
+/// # Returns
+/// The pages without duplicates, and the number of duplicates dropped.
+pub(crate) fn deduplicate_parts(
+    results: Vec<Result<Vec<SearchResult>, ScraperError>>,
+) -> (Vec<Result<Vec<SearchResult>, ScraperError>>, usize) {
+    let mut first_copies: HashMap<PartKey, (usize, usize)> = HashMap::new();
+    let mut duplicates = Vec::new();
+    let mut results: Vec<_> = results
+        .into_iter()
+        .enumerate()
+        .map(|(page_index, result)| {
+            result.map(|components| {
+                let mut kept = Vec::with_capacity(components.len());
+                for component in components {
+                    let key = component.part.key();
+                    match first_copies.get(&key) {
+                        Some(&position) => duplicates.push((position, component)),
+                        None => {
+                            first_copies.insert(key, (page_index, kept.len()));
+                            kept.push(component);
+                        }
+                    }
+                }
+                kept
+            })
+        })
+        .collect();
+
+    let duplicates_dropped = duplicates.len();
+    for ((page_index, component_index), duplicate) in duplicates {
+        if let Some(Ok(components)) = results.get_mut(page_index) {
+            merge_specs(&mut components[component_index].part, duplicate.part);
+        }
+    }
+    (results, duplicates_dropped)
+}
+
+/// Merges the specs of `duplicate` into `part`.
+fn merge_specs(part: &mut Part, duplicate: Part) {
+    for duplicate_spec in duplicate.specs {
+        match part
+            .specs
+            .iter_mut()
+            .find(|spec| spec.attribute.shortname == duplicate_spec.attribute.shortname)
+        {
+            Some(spec) => {
+                if duplicate_spec.display_value < spec.display_value {
+                    spec.display_value = duplicate_spec.display_value;
+                }
+            }
+            None => part.specs.push(duplicate_spec),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::deduplicate_parts;
+    use crate::batch_manager::request::fake_transport::FakeComponent;
+    use crate::batch_manager::request::models::SearchResult;
+    use crate::error::ScraperError;
+
+    fn component(id: &str, mpn: &str, specs: &[(&str, &str)]) -> SearchResult {
+        FakeComponent::new(id, mpn).with_specs(specs).build()
+    }
+
+    fn display_values(component: &SearchResult) -> Vec<(&str, &str)> {
+        component
+            .part
+            .specs
+            .iter()
+            .map(|spec| {
+                (
+                    spec.attribute.shortname.as_str(),
+                    spec.display_value.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drops_duplicates_and_merges_their_specs() {
+        let results = vec![
+            Ok(vec![
+                component("1", "A", &[("capacitance", "1uF"), ("tolerance", "20%")]),
+                component("", "B", &[]),
+            ]),
+            Err(ScraperError::Blocked),
+            Ok(vec![
+                component("1", "A", &[("tolerance", "10%"), ("case_package", "0805")]),
+                component("", "B", &[("capacitance", "2uF")]),
+                component("3", "B", &[]),
+            ]),
+        ];
+
+        let (results, duplicates_dropped) = deduplicate_parts(results);
+
+        assert_eq!(duplicates_dropped, 2);
+        let first_page = results[0].as_ref().unwrap();
+        assert_eq!(
+            display_values(&first_page[0]),
+            [
+                ("capacitance", "1uF"),
+                ("tolerance", "10%"),
+                ("case_package", "0805")
+            ]
+        );
+        assert_eq!(display_values(&first_page[1]), [("capacitance", "2uF")]);
+        assert!(results[1].is_err());
+        let last_page = results[2].as_ref().unwrap();
+        assert_eq!(last_page.len(), 1);
+        assert_eq!(last_page[0].part.id, "3");
+    }
+}
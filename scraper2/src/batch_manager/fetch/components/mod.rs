@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -7,12 +7,17 @@ use chrono::Utc;
 use serde_json::{json, Value};
 use tokio::sync::RwLock;
 
+mod dedupe;
 pub(crate) mod processor;
 
+use crate::batch_manager::checkpoint::Checkpoint;
+use crate::batch_manager::job::ScrapeJob;
+use crate::batch_manager::request::models::SearchResult;
 use crate::batch_manager::request::request_sender::RequestSender;
 use crate::batch_manager::request::response_handler::ResponseHandler;
 use crate::batch_manager::types::{
-    AttributeBucket, AttributeBucketCombinations, ComponentCount, ComponentCounts,
+    AttributeBucketCombination, AttributeBucketCombinations, ComponentCount, ComponentCounts,
+    ComponentSort, SortDirection,
 };
 
 use crate::cli::Arguments;
@@ -20,20 +25,27 @@ use crate::config::constants::{
     OCTOPART_COMPONENT_COMBINATION_LIMIT, OCTOPART_COMPONENT_REQUEST_LIMIT,
 };
 use crate::config::prompts::{print_error_message, print_info_message};
+use crate::data_manager::NdjsonWriter;
+use crate::error::ScraperError;
 
 use super::tasks::{TaskProcessor, TaskType};
 
+use dedupe::deduplicate_parts;
 use processor::ComponentTaskData;
 
 pub(crate) struct ComponentScraper {
     args: Arc<RwLock<Arguments>>,
+    job: Arc<ScrapeJob>,
     batch_size: usize,
     request_sender: Arc<RequestSender>,
     response_handler: Arc<ResponseHandler>,
+    checkpoint: Arc<Checkpoint>,
+    /// Receives every page as it completes when the output is streamed as NDJSON.
+    ndjson_writer: Option<Arc<NdjsonWriter>>,
     /// Holds the additional metadata from Octopart.
     ///
     /// Example:
-    /// ```
+    /// ```json
     /// {
     ///   "data": {
     ///     "search": {
@@ -60,7 +72,7 @@ pub(crate) struct ComponentScraper {
     /// Holds the additional metadata from the Scraper tool.
     ///
     /// Example:
-    /// ```
+    /// ```json
     /// {
     ///  "components_scraped": 23000,
     ///  "components_missed": 234,
@@ -69,29 +81,62 @@ pub(crate) struct ComponentScraper {
     /// }
     /// ```
     scraper_component_metadata: Option<Value>,
+
+    /// The number of parts fetched more than once and dropped from the output.
+    duplicates_dropped: usize,
+
+    /// The number of parts each page is expected to hold, by `ComponentCount::page_key`.
+    page_sizes: HashMap<String, usize>,
+
+    /// The number of pages that still failed after `MAX_TASK_FAILURES` attempts.
+    pages_failed: usize,
 }
 
 impl ComponentScraper {
     pub(crate) fn new(
         args: Arc<RwLock<Arguments>>,
+        job: Arc<ScrapeJob>,
         batch_size: usize,
         request_sender: Arc<RequestSender>,
         response_handler: Arc<ResponseHandler>,
+        checkpoint: Arc<Checkpoint>,
     ) -> Self {
         Self {
             args,
+            job,
             batch_size,
             request_sender,
             response_handler,
+            checkpoint,
+            ndjson_writer: None,
             octopart_component_metadata: None,
             scraper_component_metadata: None,
+            duplicates_dropped: 0,
+            page_sizes: HashMap::new(),
+            pages_failed: 0,
         }
     }
 
+    /// Streams every page of components to `ndjson_writer` as it completes, including the pages
+    /// recovered from a checkpoint.
+    pub(crate) fn with_ndjson_writer(mut self, ndjson_writer: Arc<NdjsonWriter>) -> Self {
+        self.ndjson_writer = Some(ndjson_writer);
+        self
+    }
+
     pub(crate) fn get_octopart_component_metadata(&self) -> Option<Value> {
         self.octopart_component_metadata.clone()
     }
 
+    /// The number of pages of the last `process` call that could not be fetched. Their parts are
+    /// missing from the output and counted as missed in the scraper metadata.
+    pub(crate) fn pages_failed(&self) -> usize {
+        self.pages_failed
+    }
+
+    /// Returns the scraper's own metadata for the job, including how many responses of each
+    /// `ResponseClass` were received, how many pages failed and how many duplicate parts were
+    /// dropped while scraping it.
     pub(crate) fn get_scraper_component_metadata(&mut self, total_time: Duration) -> Option<Value> {
         if self.scraper_component_metadata.is_none() {
             self.scraper_component_metadata = Some(json!({
@@ -101,32 +146,74 @@ impl ComponentScraper {
                 "date_collected": Utc::now().timestamp(),
             }));
         } else {
-            self.scraper_component_metadata.as_mut().map(|metadata| {
+            if let Some(metadata) = self.scraper_component_metadata.as_mut() {
                 metadata["total_time"] = json!(total_time.as_secs_f64());
-                metadata
-            });
+            }
+        }
+        if let Some(metadata) = self.scraper_component_metadata.as_mut() {
+            metadata["responses"] = json!(self.response_handler.response_counts());
+            metadata["duplicates_dropped"] = json!(self.duplicates_dropped);
+            metadata["pages_failed"] = json!(self.pages_failed);
         }
         self.scraper_component_metadata.clone()
     }
 
+    /// Fetches every page of components for the given combinations.
+    ///
+    /// # Arguments
+    /// * `attribute_bucket_combinations` - The combinations produced by the component counter.
+    /// * `completed_pages` - Pages already fetched by a previous run, which are not requested
+    ///   again.
+    ///
+    /// # Returns
+    /// The components of every page, including the completed ones, with each part appearing only
+    /// once. A page that still failed after `MAX_TASK_FAILURES` attempts holds its error instead,
+    /// and is reported by `pages_failed`. When the pages are streamed to an `NdjsonWriter`, which
+    /// drops duplicates itself, only the pages that failed are returned.
     pub(crate) async fn process(
         &mut self,
         attribute_bucket_combinations: AttributeBucketCombinations,
-    ) -> Result<Vec<Result<Vec<Value>>>> {
+        completed_pages: Vec<(ComponentCount, Vec<SearchResult>)>,
+    ) -> Result<Vec<Result<Vec<SearchResult>, ScraperError>>> {
         print_info_message("Scraping component batches...", false);
         let component_counts = self
             .create_component_counts(attribute_bucket_combinations)
             .await;
         let component_counts_to_process = self
-            .create_component_counts_to_process(component_counts)
+            .create_component_counts_to_process(component_counts, &completed_pages)
             .await?;
+        let completed_pages = match &self.ndjson_writer {
+            Some(ndjson_writer) => {
+                for (_, components) in completed_pages {
+                    ndjson_writer.append(&components).await?;
+                }
+                Vec::new()
+            }
+            None => completed_pages,
+        };
+
+        let pages: Vec<_> = component_counts_to_process
+            .iter()
+            .map(|task_data| task_data.component_count.clone())
+            .collect();
 
         let results = self
             .process_tasks(TaskType::ComponentScraper, component_counts_to_process)
             .await;
-        // Wait for the Octopart metadata.
-        if let Some(receiver) = self.response_handler.clone().take_receiver().await {
-            match receiver.await {
+        if let Ok(results) = &results {
+            self.record_failed_pages(&pages, results);
+        }
+        let results = results.map(|results| -> Vec<_> {
+            completed_pages
+                .into_iter()
+                .map(|(_, components)| Ok(components))
+                .chain(results)
+                .collect()
+        });
+        // Collect the Octopart metadata. Every task has finished by now, so it was either sent
+        // already or never will be (e.g. when every page came from a checkpoint).
+        if let Some(mut receiver) = self.response_handler.clone().take_receiver().await {
+            match receiver.try_recv() {
                 Ok(metadata) => {
                     self.octopart_component_metadata = Some(metadata);
                 }
@@ -135,20 +222,34 @@ impl ComponentScraper {
                 }
             };
         };
-        results
+        let results = match &self.ndjson_writer {
+            Some(ndjson_writer) => {
+                self.duplicates_dropped = ndjson_writer.duplicates_dropped().await;
+                results?.into_iter().filter(Result::is_err).collect()
+            }
+            None => {
+                let (results, duplicates_dropped) = deduplicate_parts(results?);
+                self.duplicates_dropped = duplicates_dropped;
+                results
+            }
+        };
+        Ok(results)
     }
 
     async fn create_component_counts_to_process(
         &self,
         component_counts: ComponentCounts,
+        completed_pages: &[(ComponentCount, Vec<SearchResult>)],
     ) -> Result<VecDeque<ComponentTaskData>, anyhow::Error> {
+        let completed_page_keys: HashSet<_> = completed_pages
+            .iter()
+            .map(|(page, _)| page.page_key())
+            .collect();
         let component_counts_to_process = component_counts
             .component_counts
-            .iter()
-            .cloned()
-            .map(|component_count| ComponentTaskData {
-                component_count: component_count.clone(),
-            })
+            .into_iter()
+            .filter(|component_count| !completed_page_keys.contains(&component_count.page_key()))
+            .map(|component_count| ComponentTaskData { component_count })
             .collect::<VecDeque<_>>();
         Ok(component_counts_to_process)
     }
@@ -158,48 +259,134 @@ impl ComponentScraper {
         attribute_bucket_combinations: AttributeBucketCombinations,
     ) -> ComponentCounts {
         let mut component_counts = Vec::new();
+        let mut page_sizes = HashMap::new();
         let mut total_components_scraped = 0;
         let mut total_components_missed = 0;
 
         for combination in attribute_bucket_combinations.combinations {
-            let mut start = 0;
             let limited_count = combination
                 .component_count
                 .min(OCTOPART_COMPONENT_COMBINATION_LIMIT);
+            let remaining_count = combination.component_count - limited_count;
 
-            total_components_scraped += limited_count;
-            if combination.component_count > OCTOPART_COMPONENT_COMBINATION_LIMIT {
-                total_components_missed +=
-                    combination.component_count - OCTOPART_COMPONENT_COMBINATION_LIMIT;
-            }
-
-            while start < limited_count {
-                let end = (start + OCTOPART_COMPONENT_REQUEST_LIMIT).min(limited_count);
-                component_counts.push(ComponentCount {
-                    attribute_bucket_combination: combination
-                        .attribute_bucket_combination
-                        .iter()
-                        .map(
-                            |(attribute_bucket_display_value, attribute_bucket)| AttributeBucket {
-                                component_count: attribute_bucket.component_count,
-                                display_value: attribute_bucket_display_value.to_string(),
-                                float_value: attribute_bucket.float_value.clone(),
-                            },
-                        )
-                        .collect(),
-                    start,
-                    end: OCTOPART_COMPONENT_REQUEST_LIMIT,
-                });
-
-                start = end;
+            match &self.job.sort_sweep_key {
+                // Fetch the first results ascending, then the remainder from the other end.
+                Some(sort_key) if remaining_count > 0 => {
+                    let swept_count = remaining_count.min(OCTOPART_COMPONENT_COMBINATION_LIMIT);
+                    for (direction, count) in [
+                        (SortDirection::Ascending, limited_count),
+                        (SortDirection::Descending, swept_count),
+                    ] {
+                        let sort = ComponentSort {
+                            key: sort_key.clone(),
+                            direction,
+                        };
+                        Self::push_component_pages(
+                            &mut component_counts,
+                            &mut page_sizes,
+                            &combination,
+                            count,
+                            Some(sort),
+                        );
+                    }
+                    total_components_scraped += limited_count + swept_count;
+                    total_components_missed += remaining_count - swept_count;
+                }
+                _ => {
+                    Self::push_component_pages(
+                        &mut component_counts,
+                        &mut page_sizes,
+                        &combination,
+                        limited_count,
+                        None,
+                    );
+                    total_components_scraped += limited_count;
+                    total_components_missed += remaining_count;
+                }
             }
         }
 
+        self.page_sizes = page_sizes;
         self.fill_scraper_metadata(total_components_scraped, total_components_missed);
 
         ComponentCounts { component_counts }
     }
 
+    /// Adds the page requests needed to fetch `count` components of a combination, and the number
+    /// of parts each page is expected to hold to `page_sizes`.
+    fn push_component_pages(
+        component_counts: &mut Vec<ComponentCount>,
+        page_sizes: &mut HashMap<String, usize>,
+        combination: &AttributeBucketCombination,
+        count: usize,
+        sort: Option<ComponentSort>,
+    ) {
+        let mut start = 0;
+        while start < count {
+            let end = (start + OCTOPART_COMPONENT_REQUEST_LIMIT).min(count);
+            let component_count = ComponentCount {
+                attribute_bucket_combination: combination.attribute_bucket_combination.clone(),
+                start,
+                end: OCTOPART_COMPONENT_REQUEST_LIMIT,
+                sort: sort.clone(),
+            };
+            page_sizes.insert(component_count.page_key(), end - start);
+            component_counts.push(component_count);
+
+            start = end;
+        }
+    }
+
+    /// Counts the parts of the pages that failed for good as missed instead of scraped, and tells
+    /// the user which pages they were.
+    ///
+    /// # Arguments
+    /// * `pages` - The pages that were requested.
+    /// * `results` - The result of every page, in the order of `pages`.
+    fn record_failed_pages(
+        &mut self,
+        pages: &[ComponentCount],
+        results: &[Result<Vec<SearchResult>, ScraperError>],
+    ) {
+        let failed_pages: Vec<_> = pages
+            .iter()
+            .zip(results)
+            .filter_map(|(page, result)| result.as_ref().err().map(|error| (page, error)))
+            .collect();
+        self.pages_failed = failed_pages.len();
+        if failed_pages.is_empty() {
+            return;
+        }
+
+        let components_failed: usize = failed_pages
+            .iter()
+            .map(|(page, _)| {
+                self.page_sizes
+                    .get(&page.page_key())
+                    .copied()
+                    .unwrap_or(page.end)
+            })
+            .sum();
+        if let Some(metadata) = self.scraper_component_metadata.as_mut() {
+            let scraped = metadata["components_scraped"].as_u64().unwrap_or(0) as usize;
+            let missed = metadata["components_missed"].as_u64().unwrap_or(0) as usize;
+            metadata["components_scraped"] = json!(scraped.saturating_sub(components_failed));
+            metadata["components_missed"] = json!(missed + components_failed);
+        }
+
+        let failed_pages: Vec<_> = failed_pages
+            .iter()
+            .map(|(page, error)| format!("{} ({})", page.page_key(), error))
+            .collect();
+        print_error_message(&format!(
+            "{} page(s) of {} with up to {} components could not be fetched: {}",
+            failed_pages.len(),
+            self.job.category_name,
+            components_failed,
+            failed_pages.join("; ")
+        ));
+    }
+
     fn fill_scraper_metadata(&mut self, components_scraped: usize, components_missed: usize) {
         self.scraper_component_metadata = Some(json!({
             "components_scraped": components_scraped,
@@ -208,3 +395,278 @@ impl ComponentScraper {
         }));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::ComponentScraper;
+    use crate::batch_manager::checkpoint::Checkpoint;
+    use crate::batch_manager::job::ScrapeJob;
+    use crate::batch_manager::request::fake_transport::{fake_part, FakeContext, FakeTransport};
+    use crate::batch_manager::request::models::SearchResult;
+    use crate::batch_manager::types::{
+        AttributeBucket, AttributeBucketCombination, AttributeBucketCombinations, ComponentCount,
+    };
+    use crate::data_manager::NdjsonWriter;
+
+    fn capacitance_combinations(component_count: usize) -> AttributeBucketCombinations {
+        let bucket = AttributeBucket {
+            component_count,
+            display_value: "1uF".to_string(),
+            float_value: Some(1e-6.to_string()),
+        };
+        AttributeBucketCombinations {
+            combinations: vec![AttributeBucketCombination {
+                attribute_bucket_combination: HashMap::from([("capacitance".to_string(), bucket)]),
+                component_count,
+            }],
+        }
+    }
+
+    fn capacitance_context(part_count: usize, sort_sweep_key: Option<&str>) -> FakeContext {
+        let parts = (0..part_count)
+            .map(|id| fake_part(id, "6332", &[("capacitance", "1uF", Some(1e-6))]))
+            .collect();
+        let job = ScrapeJob::new("Ceramic Capacitors", &["Capacitance".to_string()], &[]).unwrap();
+        let job = ScrapeJob {
+            sort_sweep_key: sort_sweep_key.map(str::to_string),
+            ..job
+        };
+        FakeContext::new(FakeTransport::new(parts), job)
+    }
+
+    async fn scrape(
+        context: &FakeContext,
+        attribute_bucket_combinations: AttributeBucketCombinations,
+    ) -> (Vec<SearchResult>, Option<Value>) {
+        let save_dir = tempfile::tempdir().unwrap();
+        let checkpoint = Checkpoint::create(
+            save_dir.path(),
+            &context.job,
+            &attribute_bucket_combinations,
+        )
+        .await
+        .unwrap();
+        scrape_with_checkpoint(
+            context,
+            checkpoint,
+            attribute_bucket_combinations,
+            Vec::new(),
+        )
+        .await
+    }
+
+    async fn scrape_with_checkpoint(
+        context: &FakeContext,
+        checkpoint: Checkpoint,
+        attribute_bucket_combinations: AttributeBucketCombinations,
+        completed_pages: Vec<(ComponentCount, Vec<SearchResult>)>,
+    ) -> (Vec<SearchResult>, Option<Value>) {
+        let mut component_scraper = ComponentScraper::new(
+            context.args.clone(),
+            context.job.clone(),
+            10,
+            context.request_sender.clone(),
+            context.response_handler.clone(),
+            Arc::new(checkpoint),
+        );
+        let components = component_scraper
+            .process(attribute_bucket_combinations, completed_pages)
+            .await
+            .unwrap()
+            .into_iter()
+            .flat_map(Result::unwrap)
+            .collect();
+        let scraper_metadata = component_scraper.get_scraper_component_metadata(Default::default());
+        (components, scraper_metadata)
+    }
+
+    fn unique_part_ids(components: &[SearchResult]) -> HashSet<&str> {
+        components
+            .iter()
+            .map(|component| component.part.id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn scrapes_every_page_of_a_combination() {
+        let context = capacitance_context(250, None);
+
+        let (components, scraper_metadata) = scrape(&context, capacitance_combinations(250)).await;
+
+        assert_eq!(components.len(), 250);
+        assert_eq!(unique_part_ids(&components).len(), 250);
+        let scraper_metadata = scraper_metadata.unwrap();
+        assert_eq!(scraper_metadata["components_scraped"], 250);
+        assert_eq!(scraper_metadata["components_missed"], 0);
+        assert_eq!(scraper_metadata["responses"]["data"], 3);
+        assert_eq!(scraper_metadata["responses"]["perimeterx_blocks"], 0);
+        assert!(context.response_handler.take_receiver().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_combination_limit_without_a_sort_sweep() {
+        let context = capacitance_context(1500, None);
+
+        let (components, scraper_metadata) = scrape(&context, capacitance_combinations(1500)).await;
+
+        assert_eq!(unique_part_ids(&components).len(), 1000);
+        assert_eq!(scraper_metadata.unwrap()["components_missed"], 500);
+    }
+
+    #[tokio::test]
+    async fn sort_sweep_recovers_results_past_the_combination_limit() {
+        let context = capacitance_context(1500, Some("mpn"));
+
+        let (components, scraper_metadata) = scrape(&context, capacitance_combinations(1500)).await;
+
+        assert_eq!(components.len(), 1500);
+        assert_eq!(unique_part_ids(&components).len(), 1500);
+        assert_eq!(scraper_metadata.unwrap()["components_missed"], 0);
+        let sort_dirs: HashSet<_> = context
+            .transport
+            .requests()
+            .iter()
+            .map(|request| request["variables"]["sort_dir"].to_string())
+            .collect();
+        assert_eq!(sort_dirs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn drops_parts_fetched_by_overlapping_combinations() {
+        let context = capacitance_context(150, None);
+        let mut combinations = capacitance_combinations(150);
+        combinations
+            .combinations
+            .push(combinations.combinations[0].clone());
+
+        let (components, scraper_metadata) = scrape(&context, combinations).await;
+
+        assert_eq!(components.len(), 150);
+        assert_eq!(unique_part_ids(&components).len(), 150);
+        assert_eq!(scraper_metadata.unwrap()["duplicates_dropped"], 150);
+    }
+
+    #[tokio::test]
+    async fn streams_pages_to_the_ndjson_writer() {
+        let save_dir = tempfile::tempdir().unwrap();
+        let context = capacitance_context(250, None);
+        let combinations = capacitance_combinations(250);
+        let checkpoint = Checkpoint::create(save_dir.path(), &context.job, &combinations)
+            .await
+            .unwrap();
+        let ndjson_path = save_dir.path().join("ceramic_capacitors.ndjson");
+        let ndjson_writer = Arc::new(NdjsonWriter::create(&ndjson_path).await.unwrap());
+
+        let mut component_scraper = ComponentScraper::new(
+            context.args.clone(),
+            context.job.clone(),
+            10,
+            context.request_sender.clone(),
+            context.response_handler.clone(),
+            Arc::new(checkpoint),
+        )
+        .with_ndjson_writer(ndjson_writer.clone());
+        let results = component_scraper
+            .process(combinations, Vec::new())
+            .await
+            .unwrap();
+        drop(component_scraper);
+
+        // The parts are only on disk.
+        assert!(results.is_empty());
+
+        let ndjson_writer = Arc::into_inner(ndjson_writer).unwrap();
+        assert_eq!(ndjson_writer.finish(&ndjson_path).await.unwrap(), (250, 0));
+        let content = std::fs::read_to_string(&ndjson_path).unwrap();
+        assert_eq!(content.lines().count(), 250);
+    }
+
+    #[tokio::test]
+    async fn resumes_without_refetching_checkpointed_pages() {
+        let save_dir = tempfile::tempdir().unwrap();
+        let context = capacitance_context(250, None);
+        let checkpoint = Checkpoint::create(
+            save_dir.path(),
+            &context.job,
+            &capacitance_combinations(250),
+        )
+        .await
+        .unwrap();
+        scrape_with_checkpoint(
+            &context,
+            checkpoint,
+            capacitance_combinations(250),
+            Vec::new(),
+        )
+        .await;
+
+        let resumed = Checkpoint::resume(save_dir.path(), &context.job)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resumed.attribute_bucket_combinations.combinations.len(), 1);
+        assert_eq!(resumed.completed_pages.len(), 3);
+
+        let resumed_context = capacitance_context(250, None);
+        let (components, _) = scrape_with_checkpoint(
+            &resumed_context,
+            resumed.checkpoint,
+            resumed.attribute_bucket_combinations,
+            resumed.completed_pages,
+        )
+        .await;
+        assert_eq!(unique_part_ids(&components).len(), 250);
+        assert!(resumed_context.transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn counts_pages_that_keep_failing_as_missed() {
+        let save_dir = tempfile::tempdir().unwrap();
+        let parts = (0..250)
+            .map(|id| fake_part(id, "6332", &[("capacitance", "1uF", Some(1e-6))]))
+            .collect();
+        let job = ScrapeJob::new("Ceramic Capacitors", &["Capacitance".to_string()], &[]).unwrap();
+        let context = FakeContext::new(FakeTransport::new(parts).with_failing_starts(&[100]), job);
+        let checkpoint = Checkpoint::create(
+            save_dir.path(),
+            &context.job,
+            &capacitance_combinations(250),
+        )
+        .await
+        .unwrap();
+        let mut component_scraper = ComponentScraper::new(
+            context.args.clone(),
+            context.job.clone(),
+            10,
+            context.request_sender.clone(),
+            context.response_handler.clone(),
+            Arc::new(checkpoint),
+        );
+
+        let results = component_scraper
+            .process(capacitance_combinations(250), Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        assert_eq!(component_scraper.pages_failed(), 1);
+        let scraper_metadata = component_scraper
+            .get_scraper_component_metadata(Default::default())
+            .unwrap();
+        assert_eq!(scraper_metadata["components_scraped"], 150);
+        assert_eq!(scraper_metadata["components_missed"], 100);
+        assert_eq!(scraper_metadata["pages_failed"], 1);
+
+        // The failed page is not in the checkpoint, so resuming fetches it again.
+        let resumed = Checkpoint::resume(save_dir.path(), &context.job)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resumed.completed_pages.len(), 2);
+    }
+}
@@ -2,16 +2,16 @@ use std::collections::{HashMap, VecDeque};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use serde_json::Value;
 use tokio::task::JoinHandle;
 
 use super::ComponentScraper;
 
 use crate::batch_manager::{
     fetch::tasks::{process_tasks_helper, TaskProcessor, TaskType},
-    request::request_sender::RequestType,
+    request::{models::SearchResult, request_sender::RequestType},
     types::ComponentCount,
 };
+use crate::error::ScraperError;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ComponentTaskData {
@@ -21,26 +21,27 @@ pub(crate) struct ComponentTaskData {
 #[async_trait]
 impl TaskProcessor for ComponentScraper {
     type TaskData = ComponentTaskData;
-    type TaskResult = Vec<Value>;
-    type TaskError = anyhow::Error;
+    type TaskResult = Vec<SearchResult>;
+    type TaskError = ScraperError;
 
     fn create_task(
         &self,
         task_data: Self::TaskData,
     ) -> JoinHandle<Result<Self::TaskResult, Self::TaskError>> {
         let mut attribute_bucket_combinations = HashMap::new();
-        for attribute_bucket in task_data
+        for (attribute_display_value, attribute_bucket) in task_data
             .component_count
             .attribute_bucket_combination
             .iter()
         {
             attribute_bucket_combinations.insert(
-                attribute_bucket.display_value.clone(),
-                vec![attribute_bucket.float_value.clone().unwrap_or_default()],
+                attribute_display_value.clone(),
+                vec![attribute_bucket.filter_value()],
             );
         }
 
         let args = self.args.clone();
+        let job = self.job.clone();
         let request_sender = self.request_sender.clone();
         let response_handler = self.response_handler.clone();
 
@@ -49,24 +50,30 @@ impl TaskProcessor for ComponentScraper {
                 .clone()
                 .send_request(
                     &*args.read().await,
+                    &job,
                     RequestType::Components {
                         filters: attribute_bucket_combinations,
                         start: task_data.component_count.start,
                         end: task_data.component_count.end,
+                        sort: task_data.component_count.sort,
                     },
+                    &response_handler,
                 )
-                .await
-                .map_err(anyhow::Error::new)?;
+                .await?;
 
             response_handler.extract_components(response).await
         })
     }
 
+    fn progress_message(&self) -> Option<String> {
+        self.request_sender.session_health_message()
+    }
+
     async fn process_tasks(
         &self,
         task_type: TaskType,
         task_data_queue: VecDeque<Self::TaskData>,
-    ) -> Result<Vec<Result<Self::TaskResult>>> {
+    ) -> Result<Vec<Result<Self::TaskResult, Self::TaskError>>> {
         process_tasks_helper(
             self,
             task_type,
@@ -76,4 +83,29 @@ impl TaskProcessor for ComponentScraper {
         )
         .await
     }
+
+    /// Pages streamed to the NDJSON writer are on disk already and are not collected.
+    fn collect_result(&self, components: Self::TaskResult) -> Self::TaskResult {
+        if self.ndjson_writer.is_some() {
+            Vec::new()
+        } else {
+            components
+        }
+    }
+
+    async fn on_task_completed(
+        &self,
+        task_data: &Self::TaskData,
+        components: &Self::TaskResult,
+    ) -> Result<()> {
+        if let Some(ndjson_writer) = &self.ndjson_writer {
+            ndjson_writer.append(components).await?;
+        }
+        self.checkpoint
+            .append_pages(vec![(
+                task_data.component_count.clone(),
+                components.clone(),
+            )])
+            .await
+    }
 }
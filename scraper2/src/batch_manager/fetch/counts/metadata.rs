@@ -1,11 +1,11 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
-use crate::batch_manager::types::AttributeBuckets;
+use crate::batch_manager::types::{AttributeBucket, AttributeBuckets};
 
 pub(crate) struct AttributeBucketMetadata {
     pub(crate) attribute_buckets: AttributeBuckets,
-    pub(crate) last_attribute_bucket_display_value: String,
-    pub(crate) second_last_attribute_bucket_display_value: Option<String>,
+    /// The split attributes, in the order the combination walk descends through them.
+    pub(crate) attribute_display_values: Vec<String>,
 }
 
 impl AttributeBucketMetadata {
@@ -13,21 +13,27 @@ impl AttributeBucketMetadata {
         attribute_display_values: Vec<String>,
         attribute_buckets: AttributeBuckets,
     ) -> Result<Self> {
-        let last_attribute_bucket_display_value = attribute_display_values
-            .last()
-            .cloned()
-            .ok_or(anyhow!("Failed to get the last attribute key"))?;
-        let mut second_last_attribute_bucket_display_value = None;
-        if attribute_buckets.buckets.len() > 1 {
-            second_last_attribute_bucket_display_value = attribute_display_values
-                .get(attribute_display_values.len() - 2)
-                .cloned();
+        if attribute_display_values.is_empty() {
+            bail!("At least one split attribute is required");
         }
 
         Ok(Self {
             attribute_buckets,
-            last_attribute_bucket_display_value,
-            second_last_attribute_bucket_display_value,
+            attribute_display_values,
         })
     }
+
+    /// Returns the unfiltered buckets of the first split attribute, which seed the walk.
+    pub(crate) fn first_attribute_buckets(&self) -> Result<(&String, &Vec<AttributeBucket>)> {
+        let first_attribute_display_value = &self.attribute_display_values[0];
+        let first_attribute_buckets = self
+            .attribute_buckets
+            .buckets
+            .get(first_attribute_display_value)
+            .ok_or(anyhow!(
+                "Failed to get the buckets of attribute `{}`",
+                first_attribute_display_value
+            ))?;
+        Ok((first_attribute_display_value, first_attribute_buckets))
+    }
 }
@@ -1,8 +1,7 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::vec;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use log::debug;
 use tokio::sync::RwLock;
 
@@ -11,13 +10,14 @@ pub(crate) mod processor;
 mod metadata;
 
 use crate::batch_manager::fetch::tasks::TaskType;
+use crate::batch_manager::job::ScrapeJob;
 use crate::batch_manager::request::request_sender::RequestSender;
 use crate::batch_manager::request::response_handler::ResponseHandler;
 use crate::batch_manager::types::{
-    AttributeBucket, AttributeBucketCombination, AttributeBucketCombinations, AttributeBucketPair,
-    AttributeBuckets,
+    AttributeBucketCombination, AttributeBucketCombinations, AttributeBuckets,
 };
 use crate::cli::Arguments;
+use crate::config::constants::OCTOPART_COMPONENT_COMBINATION_LIMIT;
 use crate::config::prompts::print_info_message;
 
 use super::tasks::TaskProcessor;
@@ -27,6 +27,7 @@ use processor::AttributeTaskData;
 
 pub(crate) struct ComponentCounter {
     args: Arc<RwLock<Arguments>>,
+    job: Arc<ScrapeJob>,
     batch_size: usize,
     attribute_bucket_metadata: AttributeBucketMetadata,
     request_sender: Arc<RequestSender>,
@@ -36,6 +37,7 @@ pub(crate) struct ComponentCounter {
 impl ComponentCounter {
     pub(crate) fn new(
         args: Arc<RwLock<Arguments>>,
+        job: Arc<ScrapeJob>,
         batch_size: usize,
         attribute_display_values: Vec<String>,
         attribute_buckets: AttributeBuckets,
@@ -47,6 +49,7 @@ impl ComponentCounter {
 
         Ok(Self {
             args,
+            job,
             batch_size,
             attribute_bucket_metadata,
             request_sender,
@@ -54,112 +57,50 @@ impl ComponentCounter {
         })
     }
 
+    /// Walks the split attributes one level at a time to find every non-empty bucket combination.
+    ///
+    /// The unfiltered buckets of the first attribute seed the walk. For every following attribute,
+    /// each combination found so far is used as a filter while requesting that attribute's buckets,
+    /// so combinations without components are pruned before the next level is expanded.
+    /// Combinations that still exceed `OCTOPART_COMPONENT_COMBINATION_LIMIT` are then split by the
+    /// job's fallback attributes.
     pub(crate) async fn process(&mut self) -> Result<AttributeBucketCombinations, anyhow::Error> {
-        let results = match self
-            .attribute_bucket_metadata
-            .attribute_buckets
-            .buckets
-            .len()
-        {
-            1 => {
-                let attribute_buckets = self
-                    .attribute_bucket_metadata
-                    .attribute_buckets
-                    .buckets
-                    .clone()
-                    .get(
-                        &self
-                            .attribute_bucket_metadata
-                            .last_attribute_bucket_display_value,
-                    )
-                    .ok_or(anyhow!("Failed to get first attribute bucket"))?
-                    .clone();
-                let mut attribute_bucket_combinations = AttributeBucketCombinations::default();
-                for attribute_bucket in attribute_buckets {
-                    let mut combination = HashMap::new();
-                    combination.insert(
-                        self.attribute_bucket_metadata
-                            .last_attribute_bucket_display_value
-                            .clone(),
-                        attribute_bucket.clone(),
-                    );
-                    attribute_bucket_combinations
-                        .combinations
-                        .push(AttributeBucketCombination {
-                            attribute_bucket_combination: combination,
-                            component_count: attribute_bucket.component_count,
-                        })
-                }
-                Ok(vec![Ok(attribute_bucket_combinations)])
-            }
-            2 | 3 => {
-                print_info_message("Counting component batches...", false);
-                let attribute_buckets_to_process = self.get_attribute_bucket_pairs().await?;
-
-                let task_data_queue: VecDeque<AttributeTaskData> = attribute_buckets_to_process
-                    .into_iter()
-                    .map(|attribute_bucket_pair| {
-                        let (attribute_bucket_display_values, attribute_buckets) =
-                            match &attribute_bucket_pair.third_last_attribute_bucket {
-                                Some((k, v)) => (
-                                    vec![
-                                        k.clone(),
-                                        attribute_bucket_pair
-                                            .second_last_attribute_bucket
-                                            .0
-                                            .clone(),
-                                    ],
-                                    vec![
-                                        v.clone(),
-                                        attribute_bucket_pair
-                                            .second_last_attribute_bucket
-                                            .1
-                                            .clone(),
-                                    ],
-                                ),
-                                None => (
-                                    vec![attribute_bucket_pair
-                                        .second_last_attribute_bucket
-                                        .0
-                                        .clone()],
-                                    vec![attribute_bucket_pair
-                                        .second_last_attribute_bucket
-                                        .1
-                                        .clone()],
-                                ),
-                            };
-
-                        AttributeTaskData {
-                            last_attribute_bucket_key: self
-                                .attribute_bucket_metadata
-                                .last_attribute_bucket_display_value
-                                .clone(),
-                            attribute_bucket_display_values,
-                            attribute_buckets,
-                        }
-                    })
-                    .collect();
-
-                self.process_tasks(TaskType::ComponentCounter, task_data_queue)
-                    .await
-            }
-            _ => Ok(Vec::new()),
-        };
+        let (first_attribute_display_value, first_attribute_buckets) =
+            self.attribute_bucket_metadata.first_attribute_buckets()?;
+
         let mut attribute_bucket_combinations = AttributeBucketCombinations::default();
-        let results = results?;
-        for result in results {
-            match result {
-                Ok(new_combination) => {
-                    attribute_bucket_combinations
-                        .combinations
-                        .extend(new_combination.combinations);
-                }
-                Err(err) => {
-                    return Err(err);
-                }
-            }
+        for attribute_bucket in first_attribute_buckets {
+            let mut combination = HashMap::new();
+            combination.insert(
+                first_attribute_display_value.clone(),
+                attribute_bucket.clone(),
+            );
+            attribute_bucket_combinations
+                .combinations
+                .push(AttributeBucketCombination {
+                    attribute_bucket_combination: combination,
+                    component_count: attribute_bucket.component_count,
+                })
+        }
+
+        let next_attribute_display_values =
+            self.attribute_bucket_metadata.attribute_display_values[1..].to_vec();
+        if !next_attribute_display_values.is_empty() {
+            print_info_message("Counting component batches...", false);
+        }
+        for next_attribute_display_value in next_attribute_display_values {
+            attribute_bucket_combinations = self
+                .expand_attribute_bucket_combinations(
+                    attribute_bucket_combinations,
+                    &next_attribute_display_value,
+                )
+                .await?;
         }
 
+        let attribute_bucket_combinations = self
+            .split_over_limit_combinations(attribute_bucket_combinations)
+            .await?;
+
         for combination in &attribute_bucket_combinations.combinations {
             debug!(
                 "Combination: {:?}, Count: {}",
@@ -169,97 +110,256 @@ impl ComponentCounter {
         Ok(attribute_bucket_combinations)
     }
 
-    async fn get_attribute_bucket_pairs(
+    /// Splits every non-empty combination by the buckets of the next attribute.
+    ///
+    /// # Arguments
+    /// * `attribute_bucket_combinations` - The combinations found at the previous level.
+    /// * `next_attribute_display_value` - The attribute to split each combination by.
+    ///
+    /// # Returns
+    /// The combinations one level deeper, or an error if any count request failed.
+    async fn expand_attribute_bucket_combinations(
         &self,
-    ) -> Result<VecDeque<AttributeBucketPair>, anyhow::Error> {
-        let last_attribute_bucket_display_value = &self
-            .attribute_bucket_metadata
-            .last_attribute_bucket_display_value;
-        let second_last_attribute_bucket_display_value = self
-            .attribute_bucket_metadata
-            .second_last_attribute_bucket_display_value
-            .as_ref()
-            .ok_or(anyhow!(
-                "Failed to get second last attribute bucket display value"
-            ))?;
-
-        let attribute_buckets: &HashMap<String, Vec<AttributeBucket>> =
-            &self.attribute_bucket_metadata.attribute_buckets.buckets;
-        let attribute_buckets_without_last_two_attributes: HashMap<_, _> = attribute_buckets
-            .iter()
-            .filter(|(key, _)| {
-                ![
-                    second_last_attribute_bucket_display_value,
-                    last_attribute_bucket_display_value,
-                ]
-                .contains(key)
+        attribute_bucket_combinations: AttributeBucketCombinations,
+        next_attribute_display_value: &str,
+    ) -> Result<AttributeBucketCombinations, anyhow::Error> {
+        let task_data_queue: VecDeque<AttributeTaskData> = attribute_bucket_combinations
+            .combinations
+            .into_iter()
+            .filter(|combination| combination.component_count > 0)
+            .map(|combination| {
+                let (attribute_bucket_display_values, attribute_buckets) =
+                    combination.attribute_bucket_combination.into_iter().unzip();
+                AttributeTaskData {
+                    last_attribute_bucket_key: next_attribute_display_value.to_string(),
+                    attribute_bucket_display_values,
+                    attribute_buckets,
+                }
             })
             .collect();
 
-        if attribute_buckets_without_last_two_attributes.is_empty() {
-            Ok(self.create_pairs_for_single_attribute_bucket(
-                attribute_buckets,
-                second_last_attribute_bucket_display_value,
-            ))
-        } else {
-            Ok(self.create_pairs_for_multiple_attribute_buckets(
-                attribute_buckets,
-                &attribute_buckets_without_last_two_attributes,
-                second_last_attribute_bucket_display_value,
-            ))
+        let mut next_attribute_bucket_combinations = AttributeBucketCombinations::default();
+        for result in self
+            .process_tasks(TaskType::ComponentCounter, task_data_queue)
+            .await?
+        {
+            next_attribute_bucket_combinations
+                .combinations
+                .extend(result?.combinations);
         }
+        Ok(next_attribute_bucket_combinations)
     }
 
-    fn create_pairs_for_single_attribute_bucket(
+    /// Repeatedly splits combinations over `OCTOPART_COMPONENT_COMBINATION_LIMIT` by the job's
+    /// fallback attributes, in order, until every combination fits or the attributes run out.
+    ///
+    /// A split only replaces its parent when the resulting combinations reach more components than
+    /// the parent did, since components without a value for the fallback attribute fall out of
+    /// every bucket.
+    ///
+    /// # Arguments
+    /// * `attribute_bucket_combinations` - The combinations produced by the split attributes.
+    ///
+    /// # Returns
+    /// The combinations after splitting, or an error if any count request failed.
+    async fn split_over_limit_combinations(
         &self,
-        attribute_buckets: &HashMap<String, Vec<AttributeBucket>>,
-        second_last_attribute_bucket_display_value: &String,
-    ) -> VecDeque<AttributeBucketPair> {
-        attribute_buckets
-            .get(second_last_attribute_bucket_display_value)
-            .unwrap_or(&vec![])
-            .iter()
-            .map(|second_last_attribute_bucket| AttributeBucketPair {
-                third_last_attribute_bucket: None,
-                second_last_attribute_bucket: (
-                    second_last_attribute_bucket_display_value.clone(),
-                    second_last_attribute_bucket.clone(),
+        attribute_bucket_combinations: AttributeBucketCombinations,
+    ) -> Result<AttributeBucketCombinations, anyhow::Error> {
+        let mut combinations = attribute_bucket_combinations.combinations;
+
+        for fallback_attribute in &self.job.split_fallback_shortnames {
+            let (over_limit, mut within_limit): (Vec<_>, Vec<_>) =
+                combinations.into_iter().partition(|combination| {
+                    combination.component_count > OCTOPART_COMPONENT_COMBINATION_LIMIT
+                });
+            if over_limit.is_empty() {
+                combinations = within_limit;
+                break;
+            }
+
+            // Combinations already filtered by this attribute cannot be split by it again.
+            let (splittable, mut unsplittable): (Vec<_>, Vec<_>) =
+                over_limit.into_iter().partition(|combination| {
+                    !combination
+                        .attribute_bucket_combination
+                        .contains_key(fallback_attribute)
+                });
+            if splittable.is_empty() {
+                within_limit.extend(unsplittable);
+                combinations = within_limit;
+                continue;
+            }
+
+            print_info_message(
+                &format!(
+                    "Splitting {} combination(s) over the result limit by `{}`...",
+                    splittable.len(),
+                    fallback_attribute
                 ),
-            })
-            .collect()
-    }
+                false,
+            );
+            let children = self
+                .expand_attribute_bucket_combinations(
+                    AttributeBucketCombinations {
+                        combinations: splittable.clone(),
+                    },
+                    fallback_attribute,
+                )
+                .await?;
+            let mut children_by_parent: HashMap<_, Vec<AttributeBucketCombination>> =
+                HashMap::new();
+            for child in children.combinations {
+                children_by_parent
+                    .entry(child.key_without(fallback_attribute))
+                    .or_default()
+                    .push(child);
+            }
 
-    fn create_pairs_for_multiple_attribute_buckets(
-        &self,
-        attribute_buckets: &HashMap<String, Vec<AttributeBucket>>,
-        attribute_buckets_without_last_two_attributes: &HashMap<&String, &Vec<AttributeBucket>>,
-        second_last_attribute_bucket_display_value: &String,
-    ) -> VecDeque<AttributeBucketPair> {
-        let mut bucket_pairs = VecDeque::new();
-
-        for (&third_last_attribute_bucket_display_value, &third_last_attribute_buckets) in
-            attribute_buckets_without_last_two_attributes
-        {
-            let second_last_attribute_buckets = attribute_buckets
-                .get(second_last_attribute_bucket_display_value)
-                .expect("Failed to get second to last attribute's buckets");
-
-            for third_last_attribute_bucket in third_last_attribute_buckets {
-                for second_last_attribute_bucket in second_last_attribute_buckets {
-                    bucket_pairs.push_back(AttributeBucketPair {
-                        third_last_attribute_bucket: Some((
-                            third_last_attribute_bucket_display_value.clone(),
-                            third_last_attribute_bucket.clone(),
-                        )),
-                        second_last_attribute_bucket: (
-                            second_last_attribute_bucket_display_value.clone(),
-                            second_last_attribute_bucket.clone(),
-                        ),
-                    });
+            for parent in splittable {
+                match children_by_parent.remove(&parent.key_without(fallback_attribute)) {
+                    Some(children)
+                        if Self::reachable_count(&children)
+                            > Self::reachable_count(std::slice::from_ref(&parent)) =>
+                    {
+                        within_limit.extend(children)
+                    }
+                    _ => unsplittable.push(parent),
                 }
             }
+            within_limit.extend(unsplittable);
+            combinations = within_limit;
         }
 
-        bucket_pairs
+        Ok(AttributeBucketCombinations { combinations })
+    }
+
+    /// The number of components that can be fetched from the given combinations.
+    fn reachable_count(combinations: &[AttributeBucketCombination]) -> usize {
+        combinations
+            .iter()
+            .map(|combination| {
+                combination
+                    .component_count
+                    .min(OCTOPART_COMPONENT_COMBINATION_LIMIT)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComponentCounter;
+    use crate::batch_manager::fetch::attributes::AttributeScraper;
+    use crate::batch_manager::job::ScrapeJob;
+    use crate::batch_manager::request::fake_transport::{fake_part, FakeContext, FakeTransport};
+    use crate::batch_manager::types::AttributeBucketCombinations;
+
+    async fn count(context: &FakeContext) -> AttributeBucketCombinations {
+        let attribute_buckets = AttributeScraper::new(
+            context.args.clone(),
+            context.job.clone(),
+            context.request_sender.clone(),
+            context.response_handler.clone(),
+        )
+        .process(&context.job.attribute_shortnames)
+        .await
+        .unwrap();
+        ComponentCounter::new(
+            context.args.clone(),
+            context.job.clone(),
+            10,
+            context.job.attribute_shortnames.clone(),
+            attribute_buckets,
+            context.request_sender.clone(),
+            context.response_handler.clone(),
+        )
+        .unwrap()
+        .process()
+        .await
+        .unwrap()
+    }
+
+    fn total_count(combinations: &AttributeBucketCombinations) -> usize {
+        combinations
+            .combinations
+            .iter()
+            .map(|combination| combination.component_count)
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn counts_a_single_split_attribute() {
+        let job = ScrapeJob::new("Mica Capacitors", &["Capacitance".to_string()], &[]).unwrap();
+        let context = FakeContext::new(FakeTransport::mica_capacitors(), job);
+
+        let combinations = count(&context).await;
+
+        assert_eq!(combinations.combinations.len(), 3);
+        assert_eq!(total_count(&combinations), 13);
+        // The attribute buckets already hold the counts, so no counting requests are needed.
+        assert_eq!(context.transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn walks_every_split_attribute() {
+        let attribute_names = [
+            "Capacitance".to_string(),
+            "Voltage Rating (DC)".to_string(),
+            "Case/Package".to_string(),
+            "Tolerance".to_string(),
+        ];
+        let job = ScrapeJob::new("Mica Capacitors", &attribute_names, &[]).unwrap();
+        let context = FakeContext::new(FakeTransport::mica_capacitors(), job);
+
+        let combinations = count(&context).await;
+
+        // Only parts with a tolerance make it through all four levels.
+        assert_eq!(combinations.combinations.len(), 2);
+        assert_eq!(total_count(&combinations), 2);
+        assert!(combinations
+            .combinations
+            .iter()
+            .all(|combination| combination.attribute_bucket_combination.len() == 4));
+    }
+
+    #[tokio::test]
+    async fn splits_over_limit_combinations_by_fallback_attributes() {
+        let parts = (0..2500)
+            .map(|id| {
+                let case_package = format!("{:04}", id % 5);
+                fake_part(
+                    id,
+                    "6332",
+                    &[
+                        ("capacitance", "1uF", Some(1e-6)),
+                        ("case_package", &case_package, None),
+                    ],
+                )
+            })
+            .collect();
+        let job = ScrapeJob::new(
+            "Ceramic Capacitors",
+            &["Capacitance".to_string()],
+            &["tolerance".to_string(), "case_package".to_string()],
+        )
+        .unwrap();
+        let context = FakeContext::new(FakeTransport::new(parts), job);
+
+        let combinations = count(&context).await;
+
+        assert_eq!(combinations.combinations.len(), 5);
+        assert_eq!(total_count(&combinations), 2500);
+        assert!(combinations
+            .combinations
+            .iter()
+            .all(|combination| combination.component_count == 500));
+    }
+
+    #[test]
+    fn rejects_too_many_split_attributes() {
+        let attribute_names = vec!["Capacitance".to_string(); 6];
+        assert!(ScrapeJob::new("Mica Capacitors", &attribute_names, &[]).is_err());
+        assert!(ScrapeJob::new("Mica Capacitors", &[], &[]).is_err());
     }
 }
@@ -8,6 +8,7 @@ use tokio::task::JoinHandle;
 use crate::batch_manager::fetch::tasks::{process_tasks_helper, TaskProcessor, TaskType};
 use crate::batch_manager::request::request_sender::RequestType;
 use crate::batch_manager::types::{AttributeBucket, AttributeBucketCombinations};
+use crate::error::ScraperError;
 
 use super::ComponentCounter;
 
@@ -22,7 +23,7 @@ pub(crate) struct AttributeTaskData {
 impl TaskProcessor for ComponentCounter {
     type TaskData = AttributeTaskData;
     type TaskResult = AttributeBucketCombinations;
-    type TaskError = anyhow::Error;
+    type TaskError = ScraperError;
 
     fn create_task(
         &self,
@@ -36,14 +37,12 @@ impl TaskProcessor for ComponentCounter {
         {
             attribute_bucket_combinations.insert(
                 attribute_display_value.clone(),
-                vec![attribute_bucket
-                    .float_value
-                    .clone()
-                    .unwrap_or(attribute_bucket.display_value.clone())],
+                vec![attribute_bucket.filter_value()],
             );
         }
 
         let args = self.args.clone();
+        let job = self.job.clone();
         let request_sender = self.request_sender.clone();
         let response_handler = self.response_handler.clone();
 
@@ -52,13 +51,14 @@ impl TaskProcessor for ComponentCounter {
                 .clone()
                 .send_request(
                     &*args.read().await,
+                    &job,
                     RequestType::ComponentCount {
                         attributes: Some(vec![task_data.last_attribute_bucket_key.clone()]),
                         filters: Some(attribute_bucket_combinations),
                     },
+                    &response_handler,
                 )
-                .await
-                .map_err(anyhow::Error::new)?;
+                .await?;
 
             let attribute_buckets = task_data.attribute_buckets;
             let current_attribute_bucket_combinations = task_data
@@ -75,15 +75,18 @@ impl TaskProcessor for ComponentCounter {
                     task_data.last_attribute_bucket_key,
                 )
                 .await
-                .map_err(anyhow::Error::new)
         })
     }
 
+    fn progress_message(&self) -> Option<String> {
+        self.request_sender.session_health_message()
+    }
+
     async fn process_tasks(
         &self,
         task_type: TaskType,
         task_data_queue: VecDeque<Self::TaskData>,
-    ) -> Result<Vec<Result<Self::TaskResult>>> {
+    ) -> Result<Vec<Result<Self::TaskResult, Self::TaskError>>> {
         process_tasks_helper(
             self,
             task_type,
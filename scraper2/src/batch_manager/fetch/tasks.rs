@@ -1,14 +1,25 @@
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
 use indicatif::ProgressBar;
 use log::debug;
-use tokio::{sync::RwLock, task::JoinHandle};
+use tokio::{
+    sync::RwLock,
+    task::{AbortHandle, JoinError, JoinHandle},
+};
 
 use crate::{
     cli::Arguments,
-    config::prompts::{print_task_error_message, LAZY_PROGRESS_STYLE},
+    config::{
+        constants::MAX_TASK_FAILURES,
+        prompts::{print_task_error_message, LAZY_PROGRESS_STYLE},
+    },
+    error::ScraperError,
 };
 
 #[derive(Debug, Clone)]
@@ -20,7 +31,7 @@ pub(crate) enum TaskType {
 
 /// A trait defining the processing behavior for tasks in an asynchronous context.
 ///
-/// This trait is designed to be implemented by types that process batches of tasks asynchronously.
+/// This trait is designed to be implemented by types that process queues of tasks asynchronously.
 /// It provides a generic interface for creating and processing tasks.
 #[async_trait]
 pub(crate) trait TaskProcessor {
@@ -29,7 +40,7 @@ pub(crate) trait TaskProcessor {
     /// The type of result produced by each task.
     type TaskResult: Sync + Send;
     /// The type of error that can occur while processing a task.
-    type TaskError: From<anyhow::Error> + Send + Sync + 'static;
+    type TaskError: From<JoinError> + Send + Sync + 'static;
 
     /// Creates a task for asynchronous execution.
     ///
@@ -56,123 +67,288 @@ pub(crate) trait TaskProcessor {
         task_type: TaskType,
         task_data_queue: VecDeque<Self::TaskData>,
     ) -> Result<Vec<Result<Self::TaskResult, Self::TaskError>>>;
+
+    /// A short status shown next to the progress bar, refreshed as tasks complete. The default
+    /// implementation shows nothing.
+    fn progress_message(&self) -> Option<String> {
+        None
+    }
+
+    /// What `process_tasks_helper` keeps of a result once `on_task_completed` has handled it. The
+    /// default implementation keeps all of it; a processor that already passed the result on can
+    /// keep an empty one, so completed results do not pile up in memory.
+    fn collect_result(&self, task_result: Self::TaskResult) -> Self::TaskResult {
+        task_result
+    }
+
+    /// Called as each task completes successfully, before its result is collected. The default
+    /// implementation does nothing.
+    ///
+    /// # Arguments
+    /// * `task_data` - The data of the completed task.
+    /// * `task_result` - The result of the completed task.
+    async fn on_task_completed(
+        &self,
+        _task_data: &Self::TaskData,
+        _task_result: &Self::TaskResult,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
-/// Joins a vector of asynchronous tasks and returns their results.
-///
-/// This function is used to await the completion of a set of tasks and collect their results.
-///
-/// # Arguments
-/// * `current_tasks` - A vector of tuples containing `JoinHandle`s and an associated data type.
-///
-/// # Returns
-/// A vector of results from the completed tasks.
+/// The tasks running in `process_tasks_helper`, by their position in the queue.
 ///
-/// # Errors
-/// If any task fails, an error is returned in place of its result.
-pub(crate) async fn join_current_tasks<T, E>(
-    current_tasks: Vec<(JoinHandle<Result<T, E>>, impl Send)>,
-) -> Vec<Result<T, E>>
-where
-    T: Sync + Send + 'static,
-    E: From<anyhow::Error>,
-{
-    let futures: Vec<_> = current_tasks
-        .into_iter()
-        .map(|(handle, _)| handle)
-        .collect();
-
-    let task_results = futures::future::join_all(futures).await;
-
-    task_results
-        .into_iter()
-        .map(|task_result| match task_result {
-            Ok(ok_value) => ok_value,
-            Err(join_error) => Err(anyhow!(join_error).into()),
-        })
-        .collect()
+/// Dropping a `JoinHandle` detaches its task instead of cancelling it, so the tasks still running
+/// when processing stops early are aborted on drop rather than left sending requests.
+#[derive(Default)]
+struct RunningTasks(HashMap<usize, AbortHandle>);
+
+impl Drop for RunningTasks {
+    fn drop(&mut self) {
+        for abort_handle in self.0.values() {
+            abort_handle.abort();
+        }
+    }
 }
 
-/// Processes a queue of tasks in batches, handling failures and retries.
+/// Processes a queue of tasks with bounded concurrency, handling failures and retries.
 ///
-/// This function takes a queue of tasks and processes them in batches up to a specified size.
-/// It also handles task failures by prompting for user intervention and retrying failed tasks.
+/// Up to `max_in_flight` tasks run at once; a new task is started as soon as any running task
+/// finishes, and the progress bar advances with every completed task.
+///
+/// Failed tasks are handled according to their `ScraperError`:
+/// * When PerimeterX blocks a task, no new tasks are started until the running ones have
+///   finished, the user is asked for a new PX key once, and the blocked tasks are retried first.
+/// * Retryable errors are retried at the back of the queue without prompting; a task that fails
+///   that way `MAX_TASK_FAILURES` times is not retried again and its error is returned in place
+///   of its result.
+/// * Any other error (e.g. a GraphQL error) would fail every task the same way, so processing
+///   stops, the tasks still running are aborted and the error is returned.
 ///
 /// # Arguments
 /// * `task_type` - The type of task being processed.
 /// * `processor` - The task processor implementing the `TaskProcessor` trait.
 /// * `task_data_queue` - A queue of task data to be processed.
-/// * `batch_size` - The maximum number of tasks to process in a single batch.
+/// * `max_in_flight` - The maximum number of tasks running at the same time.
 /// * `args` - Shared application arguments, used for user prompts.
 ///
 /// # Returns
-/// A vector of results from all processed tasks.
+/// The result of every task, in the order of `task_data_queue`.
 ///
 /// # Errors
-/// Returns an error if any task in the batch fails after retrying.
+/// Returns an error if a task fails with an error that cannot be retried, the user could not be
+/// prompted for a new PX key, or `TaskProcessor::on_task_completed` fails.
 pub(crate) async fn process_tasks_helper<T>(
     processor: &T,
     task_type: TaskType,
     task_data_queue: VecDeque<T::TaskData>,
-    batch_size: usize,
+    max_in_flight: usize,
     args: Arc<RwLock<Arguments>>,
 ) -> Result<Vec<Result<T::TaskResult, T::TaskError>>>
 where
-    T: TaskProcessor + Sync + 'static,
+    T: TaskProcessor<TaskError = ScraperError> + Sync + 'static,
 {
-    let mut results = Vec::new();
-    let mut tasks = Vec::new();
-    let mut task_data_queue = task_data_queue;
-
     let total_tasks = task_data_queue.len();
     let progress_bar = ProgressBar::new(total_tasks as u64);
     progress_bar.set_style(LAZY_PROGRESS_STYLE.clone());
 
-    while !task_data_queue.is_empty() {
-        if let Some(task_data) = task_data_queue.pop_front() {
+    // Tasks carry their position in the queue so results can be returned in queue order.
+    let mut pending: VecDeque<_> = task_data_queue.into_iter().enumerate().collect();
+    let mut failures = vec![0; total_tasks];
+    let mut results: Vec<_> = (0..total_tasks).map(|_| None).collect();
+    let mut blocked_tasks = Vec::new();
+    let mut running_tasks = FuturesUnordered::new();
+    let mut abort_handles = RunningTasks::default();
+
+    loop {
+        while blocked_tasks.is_empty() && running_tasks.len() < max_in_flight.max(1) {
+            let Some((index, task_data)) = pending.pop_front() else {
+                break;
+            };
             let task = processor.create_task(task_data.clone());
-            tasks.push((task, task_data));
+            abort_handles.0.insert(index, task.abort_handle());
+            running_tasks.push(async move { (index, task_data, task.await) });
         }
 
-        if tasks.len() >= batch_size || task_data_queue.is_empty() {
-            debug!("Tasks left: {}", task_data_queue.len());
-            progress_bar.set_position((total_tasks - task_data_queue.len()) as u64);
-
-            let associated_task_data: Vec<_> = tasks
-                .iter()
-                .map(|(_, task_data)| task_data.clone())
-                .collect();
-            let batch_results = join_current_tasks(std::mem::take(&mut tasks)).await;
-            let batch_results_with_task_data: Vec<_> = batch_results
-                .into_iter()
-                .zip(associated_task_data)
-                .collect();
-
-            let failed_tasks: Vec<_> = batch_results_with_task_data
-                .iter()
-                .filter_map(|(res, task_data)| {
-                    if res.is_err() {
-                        Some(task_data.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            if !failed_tasks.is_empty() {
-                print_task_error_message(&task_type, failed_tasks.len());
-                args.write().await.prompt_user_for_new_px_key();
-                task_data_queue.extend(failed_tasks.into_iter()); // FIX THIS
-            } else {
-                results.extend(
-                    batch_results_with_task_data
-                        .into_iter()
-                        .map(|(result, _)| result),
-                );
+        let Some((index, task_data, task_result)) = running_tasks.next().await else {
+            if blocked_tasks.is_empty() {
+                break;
+            }
+            print_task_error_message(&task_type, blocked_tasks.len());
+            args.write().await.prompt_user_for_new_px_key()?;
+            for blocked_task in blocked_tasks.drain(..).rev() {
+                pending.push_front(blocked_task);
+            }
+            continue;
+        };
+        abort_handles.0.remove(&index);
+
+        if let Some(message) = processor.progress_message() {
+            progress_bar.set_message(message);
+        }
+        match task_result.unwrap_or_else(|join_error| Err(join_error.into())) {
+            Ok(task_result) => {
+                processor
+                    .on_task_completed(&task_data, &task_result)
+                    .await?;
+                results[index] = Some(Ok(processor.collect_result(task_result)));
+                progress_bar.inc(1);
+            }
+            Err(ScraperError::Blocked) => {
+                debug!("Task {} was blocked", index);
+                blocked_tasks.push((index, task_data));
+            }
+            Err(task_error) if task_error.is_retryable() => {
+                failures[index] += 1;
+                if failures[index] >= MAX_TASK_FAILURES {
+                    debug!(
+                        "Giving up on task {} after {} failures: {}",
+                        index, failures[index], task_error
+                    );
+                    results[index] = Some(Err(task_error));
+                    progress_bar.inc(1);
+                } else {
+                    debug!("Task {} failed, retrying: {}", index, task_error);
+                    pending.push_back((index, task_data));
+                }
+            }
+            Err(task_error) => {
+                return Err(anyhow!(task_error).context(format!("{:?} task failed", task_type)));
             }
         }
     }
 
     progress_bar.finish_with_message("Processing complete");
-    Ok(results)
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use tokio::sync::RwLock;
+    use tokio::task::JoinHandle;
+
+    use super::{process_tasks_helper, TaskProcessor, TaskType};
+    use crate::cli::Arguments;
+    use crate::error::ScraperError;
+
+    /// Sleeps for the given number of milliseconds and returns it, tracking how many tasks run
+    /// at the same time. The first `failures` tasks started fail with `error()` instead.
+    struct SleepProcessor {
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+        started: Arc<AtomicUsize>,
+        completed: AtomicUsize,
+        failures: usize,
+        error: fn() -> ScraperError,
+        args: Arc<RwLock<Arguments>>,
+    }
+
+    impl SleepProcessor {
+        fn new(failures: usize, error: fn() -> ScraperError) -> Self {
+            Self {
+                running: Arc::default(),
+                max_running: Arc::default(),
+                started: Arc::default(),
+                completed: AtomicUsize::new(0),
+                failures,
+                error,
+                args: Arc::new(RwLock::new(Arguments::default())),
+            }
+        }
+
+        async fn process(&self, task_data_queue: &[u64]) -> Result<Vec<Result<u64, ScraperError>>> {
+            self.process_tasks(
+                TaskType::ComponentScraper,
+                task_data_queue.iter().copied().collect(),
+            )
+            .await
+        }
+    }
+
+    #[async_trait]
+    impl TaskProcessor for SleepProcessor {
+        type TaskData = u64;
+        type TaskResult = u64;
+        type TaskError = ScraperError;
+
+        fn create_task(&self, millis: u64) -> JoinHandle<Result<u64, ScraperError>> {
+            let running = self.running.clone();
+            let max_running = self.max_running.clone();
+            let fails = self.started.fetch_add(1, Ordering::SeqCst) < self.failures;
+            let error = self.error;
+            tokio::spawn(async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if fails {
+                    return Err(error());
+                }
+                Ok(millis)
+            })
+        }
+
+        async fn process_tasks(
+            &self,
+            task_type: TaskType,
+            task_data_queue: VecDeque<u64>,
+        ) -> Result<Vec<Result<u64, ScraperError>>> {
+            process_tasks_helper(self, task_type, task_data_queue, 3, self.args.clone()).await
+        }
+
+        async fn on_task_completed(&self, _millis: &u64, _result: &u64) -> Result<()> {
+            self.completed.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_tasks_in_flight_and_returns_results_in_queue_order() {
+        let processor = SleepProcessor::new(0, || unreachable!());
+        let task_data_queue = [40, 5, 5, 30, 5, 5, 5, 20, 5, 5];
+
+        let results = processor.process(&task_data_queue).await.unwrap();
+
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(results, task_data_queue);
+        assert_eq!(processor.max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(processor.completed.load(Ordering::SeqCst), 10);
+    }
+
+    #[tokio::test]
+    async fn retries_retryable_failures_without_prompting() {
+        let processor = SleepProcessor::new(3, || {
+            ScraperError::MalformedResponse("truncated".to_string())
+        });
+
+        let results = processor.process(&[5, 5, 5, 5]).await.unwrap();
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(processor.started.load(Ordering::SeqCst), 7);
+    }
+
+    #[tokio::test]
+    async fn stops_on_errors_that_cannot_be_retried() {
+        let processor = SleepProcessor::new(1, || {
+            ScraperError::GraphQl(vec!["Unknown attribute".to_string()])
+        });
+
+        let error = processor.process(&[5, 200, 200, 200]).await.unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<ScraperError>(),
+            Some(ScraperError::GraphQl(_))
+        ));
+        // The two tasks still sleeping were aborted, so they never finish and leave `running`.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(processor.running.load(Ordering::SeqCst), 2);
+        assert_eq!(processor.started.load(Ordering::SeqCst), 3);
+    }
 }
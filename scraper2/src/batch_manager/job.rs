@@ -0,0 +1,145 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::cli::Arguments;
+use crate::config::categories::{ATTRIBUTES_MAP, CATEGORIES_MAP};
+use crate::config::constants::{DEFAULT_SPLIT_FALLBACK_ATTRIBUTES, MAX_SPLIT_ATTRIBUTES};
+use crate::error::ScraperError;
+
+/// A single category to scrape, together with the attributes it is split by.
+///
+/// A run holds one job per category; all of them share the same `RequestSender` session.
+#[derive(Clone, Debug)]
+pub(crate) struct ScrapeJob {
+    pub(crate) category_name: String,
+    pub(crate) category_id: String,
+    pub(crate) attribute_shortnames: Vec<String>,
+    /// Attribute shortnames used to split combinations that are still over the result cap.
+    pub(crate) split_fallback_shortnames: Vec<String>,
+    /// Sort key used to fetch over-limit combinations both ascending and descending, if enabled.
+    pub(crate) sort_sweep_key: Option<String>,
+}
+
+impl ScrapeJob {
+    /// Creates a job, resolving the category and attribute names against the Octopart maps.
+    ///
+    /// # Arguments
+    /// * `category_name` - A key of `CATEGORIES_MAP`.
+    /// * `attribute_names` - Keys of `ATTRIBUTES_MAP`, in the order the category is split by.
+    /// * `split_fallback_shortnames` - Values of `ATTRIBUTES_MAP`, tried in order on combinations
+    ///   that are still over the result cap.
+    ///
+    /// # Returns
+    /// A `Result` with the `ScrapeJob`, or an error if any name is unknown or the number of
+    /// attributes is not between 1 and `MAX_SPLIT_ATTRIBUTES`.
+    pub(crate) fn new(
+        category_name: &str,
+        attribute_names: &[String],
+        split_fallback_shortnames: &[String],
+    ) -> Result<Self> {
+        if attribute_names.is_empty() || attribute_names.len() > MAX_SPLIT_ATTRIBUTES {
+            bail!(
+                "{} needs between 1 and {} split attributes, got {}",
+                category_name,
+                MAX_SPLIT_ATTRIBUTES,
+                attribute_names.len()
+            );
+        }
+        Ok(Self {
+            category_name: category_name.to_string(),
+            category_id: Self::parse_category(category_name)?,
+            attribute_shortnames: Self::parse_attributes(attribute_names)?,
+            split_fallback_shortnames: Self::parse_shortnames(split_fallback_shortnames)?,
+            sort_sweep_key: None,
+        })
+    }
+
+    /// Builds the list of jobs described by the arguments.
+    ///
+    /// Jobs listed in the `--config` file take precedence over `--category`/`--attribute`.
+    pub(crate) fn from_arguments(args: &Arguments) -> Result<Vec<Self>> {
+        let split_fallback = args.split_fallback.clone().unwrap_or_else(|| {
+            DEFAULT_SPLIT_FALLBACK_ATTRIBUTES
+                .iter()
+                .map(|shortname| shortname.to_string())
+                .collect()
+        });
+
+        let jobs = if !args.jobs.is_empty() {
+            args.jobs
+                .iter()
+                .map(|job| {
+                    Self::new(
+                        &job.category,
+                        &job.attributes,
+                        job.split_fallback.as_ref().unwrap_or(&split_fallback),
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            let category_name = args
+                .category_name
+                .as_ref()
+                .ok_or_else(|| anyhow!("Category name is required"))?;
+            let attribute_names = args
+                .attribute_names
+                .as_ref()
+                .ok_or_else(|| anyhow!("Attribute names are required"))?;
+            vec![Self::new(category_name, attribute_names, &split_fallback)?]
+        };
+        // Checkpoints are named after the category and split attributes.
+        for (index, job) in jobs.iter().enumerate() {
+            if jobs[..index].iter().any(|other| {
+                other.category_name == job.category_name
+                    && other.attribute_shortnames == job.attribute_shortnames
+            }) {
+                bail!(
+                    "{} is listed twice with the same attributes; merge the two jobs",
+                    job.category_name
+                );
+            }
+        }
+
+        Ok(jobs
+            .into_iter()
+            .map(|job| Self {
+                sort_sweep_key: args.sort_sweep.clone(),
+                ..job
+            })
+            .collect())
+    }
+
+    /// Determines the category ID for the given category name.
+    fn parse_category(category_name: &str) -> Result<String, ScraperError> {
+        CATEGORIES_MAP
+            .get(category_name)
+            .map(|category_id| category_id.to_string())
+            .ok_or_else(|| ScraperError::UnknownCategory(category_name.to_string()))
+    }
+
+    /// Retrieves the attribute shortnames for the given attribute names.
+    fn parse_attributes(attribute_names: &[String]) -> Result<Vec<String>, ScraperError> {
+        attribute_names
+            .iter()
+            .map(|attribute_name| {
+                ATTRIBUTES_MAP
+                    .get(attribute_name)
+                    .map(|shortname| shortname.to_string())
+                    .ok_or_else(|| ScraperError::UnknownAttribute(attribute_name.clone()))
+            })
+            .collect()
+    }
+
+    /// Checks that every given attribute shortname is known.
+    fn parse_shortnames(shortnames: &[String]) -> Result<Vec<String>, ScraperError> {
+        shortnames
+            .iter()
+            .map(|shortname| {
+                if ATTRIBUTES_MAP.values().any(|known| known == shortname) {
+                    Ok(shortname.clone())
+                } else {
+                    Err(ScraperError::UnknownAttribute(shortname.clone()))
+                }
+            })
+            .collect()
+    }
+}
@@ -1,22 +1,37 @@
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use log::debug;
 use tokio::sync::RwLock;
 use tokio::time::Instant;
 
+mod checkpoint;
 mod fetch;
+mod job;
 mod request;
 mod types;
 
 use crate::batch_manager::fetch::attributes::AttributeScraper;
-use crate::cli::Arguments;
-use crate::data_manager::DataManager;
+use crate::cli::{Arguments, OutputFormat};
+use crate::config::constants::{DEFAULT_REQUESTS_PER_SECOND, DEFAULT_REQUEST_BURST};
+use crate::config::prompts::print_info_message;
+use crate::data_manager::{DataManager, NdjsonWriter, SavedOutput};
 
+use checkpoint::Checkpoint;
 use fetch::components::ComponentScraper;
 use fetch::counts::ComponentCounter;
+use job::ScrapeJob;
+use request::rate_limiter::RateLimiter;
 use request::request_sender::RequestSender;
 use request::response_handler::ResponseHandler;
+use request::session_pool::SessionPool;
+use request::transport::{HttpTransport, RecordingTransport, ReplayTransport, Transport};
+use types::AttributeBucketCombinations;
+
+#[cfg(test)]
+pub(crate) use request::fake_transport::FakeComponent;
+pub(crate) use request::models::{PartKey, SearchResult};
 
 pub struct BatchManager {
     args: Arc<RwLock<Arguments>>,
@@ -29,21 +44,254 @@ impl BatchManager {
         Self { batch_size, args }
     }
 
+    /// Scrapes every job described by the arguments and saves each category as it finishes.
+    ///
+    /// Jobs run one after another, or concurrently when `--interleave` is set. When more than one
+    /// category was scraped the metadata files this run wrote are combined at the end.
     pub async fn run(&mut self) -> Result<()> {
+        let (jobs, interleave, request_sender) = {
+            let args = self.args.read().await;
+            (
+                ScrapeJob::from_arguments(&args)?,
+                args.interleave,
+                Arc::new(Self::create_request_sender(&args)?),
+            )
+        };
+
+        let saved_outputs = if interleave {
+            let runs = jobs
+                .iter()
+                .map(|job| self.run_job(job.clone(), request_sender.clone()));
+            futures::future::try_join_all(runs).await?
+        } else {
+            let mut saved_outputs = Vec::with_capacity(jobs.len());
+            for job in &jobs {
+                saved_outputs.push(self.run_job(job.clone(), request_sender.clone()).await?);
+            }
+            saved_outputs
+        };
+        let saved_outputs: Vec<_> = saved_outputs.into_iter().flatten().collect();
+
+        // The database keeps every category together already. Only this run's files are combined,
+        // so the output of earlier runs in the same directory is left alone.
+        let output_format = self.args.read().await.output_format.unwrap_or_default();
+        if jobs.len() > 1 && output_format != OutputFormat::Sqlite {
+            let data_manager = DataManager::new(self.args.clone());
+            let metadata_files: Vec<_> = saved_outputs
+                .iter()
+                .filter_map(|saved_output| saved_output.metadata_filepath.clone())
+                .collect();
+            data_manager
+                .combine_metadata_filepaths(&metadata_files)
+                .await?;
+            if output_format == OutputFormat::Csv {
+                let csv_files: Vec<_> = saved_outputs
+                    .iter()
+                    .map(|saved_output| saved_output.filepath.clone())
+                    .collect();
+                data_manager.combine_csv_filepaths(&csv_files).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the request sender shared by every job, recording or replaying traffic if asked to.
+    ///
+    /// Requests to Octopart are paced by a single rate limiter; replayed requests are not.
+    fn create_request_sender(args: &Arguments) -> Result<RequestSender> {
+        if let Some(replay_dir) = &args.replay {
+            print_info_message(
+                &format!("Replaying responses from {}...", replay_dir.display()),
+                false,
+            );
+            let replay_transport = Arc::new(ReplayTransport::new(replay_dir)?);
+            return Ok(RequestSender::with_transport(replay_transport));
+        }
+
+        let http_transport = Arc::new(HttpTransport::new()?);
+        let transport: Arc<dyn Transport> = match &args.record {
+            Some(record_dir) => Arc::new(RecordingTransport::new(http_transport, record_dir)?),
+            None => http_transport,
+        };
+        let rate_limiter = RateLimiter::new(
+            args.requests_per_second
+                .unwrap_or(DEFAULT_REQUESTS_PER_SECOND),
+            args.burst.unwrap_or(DEFAULT_REQUEST_BURST),
+        )?;
+        let mut request_sender =
+            RequestSender::with_transport(transport).with_rate_limiter(rate_limiter);
+        if let Some(sessions_path) = &args.sessions {
+            request_sender = request_sender.with_session_pool(SessionPool::load(sessions_path)?);
+        }
+        Ok(request_sender)
+    }
+
+    /// Scrapes and saves one category.
+    ///
+    /// # Returns
+    /// The files the category was saved to, or `None` if the overwrite policy kept existing ones.
+    async fn run_job(
+        &self,
+        job: ScrapeJob,
+        request_sender: Arc<RequestSender>,
+    ) -> Result<Option<SavedOutput>> {
         let start = Instant::now();
 
-        let request_sender = Arc::new(RequestSender::new(&*self.args.read().await));
-        let response_handler = Arc::new(ResponseHandler::new());
+        print_info_message(&format!("Scraping {}...", job.category_name), false);
+        let job = Arc::new(job);
+        let response_handler =
+            Arc::new(ResponseHandler::new().with_strict_validation(self.args.read().await.strict));
+        let (output_dir, filename) = {
+            let args = self.args.read().await;
+            (
+                args.output_dir().to_path_buf(),
+                args.filename_template()?.render(
+                    &job.category_name,
+                    &job.attribute_shortnames,
+                    Utc::now(),
+                ),
+            )
+        };
+
+        // 1-3. Get the bucket combinations, either from a checkpoint or by counting.
+        let resumed_checkpoint = if self.args.read().await.resume {
+            Checkpoint::resume(&output_dir, &job).await?
+        } else {
+            None
+        };
+        let (checkpoint, component_counts, completed_pages) = match resumed_checkpoint {
+            Some(resumed) => {
+                print_info_message(
+                    &format!(
+                        "Resuming from checkpoint with {} completed page(s)...",
+                        resumed.completed_pages.len()
+                    ),
+                    false,
+                );
+                (
+                    resumed.checkpoint,
+                    resumed.attribute_bucket_combinations,
+                    resumed.completed_pages,
+                )
+            }
+            None => {
+                let component_counts = self
+                    .count_components(&job, &request_sender, &response_handler)
+                    .await?;
+                let checkpoint = Checkpoint::create(&output_dir, &job, &component_counts).await?;
+                (checkpoint, component_counts, Vec::new())
+            }
+        };
+        let checkpoint = Arc::new(checkpoint);
 
-        // 1. Get the attribute ids from the request sender.
-        let attribute_shortnames = request_sender
-            .get_attribute_shortnames()
-            .ok_or_else(|| anyhow!("Failed to get attribute ids"))?;
+        // 4. Get the components from the component scraper, streaming them to disk if asked to.
+        let mut component_scraper = ComponentScraper::new(
+            self.args.clone(),
+            job.clone(),
+            self.batch_size,
+            request_sender.clone(),
+            response_handler.clone(),
+            checkpoint.clone(),
+        );
+        let output_format = self.args.read().await.output_format.unwrap_or_default();
+        let ndjson_writer = match output_format {
+            OutputFormat::Ndjson => Some(Arc::new(
+                NdjsonWriter::create(&output_dir.join(format!("{}.ndjson", filename))).await?,
+            )),
+            OutputFormat::Json | OutputFormat::Csv | OutputFormat::Sqlite => None,
+        };
+        if let Some(ndjson_writer) = &ndjson_writer {
+            component_scraper = component_scraper.with_ndjson_writer(ndjson_writer.clone());
+        }
+        let components = component_scraper
+            .process(component_counts, completed_pages)
+            .await;
+        debug!("Components: {:?}", components);
+
+        // 5. Save the components to disk.
+        let mut octopart_metadata = component_scraper.get_octopart_component_metadata();
+        let scraper_time = component_scraper.get_scraper_component_metadata(start.elapsed());
+        let response_counts = response_handler.response_counts();
+        if response_counts.perimeterx_challenges + response_counts.perimeterx_blocks > 0 {
+            print_info_message(
+                &format!(
+                    "PerimeterX blocked {} of {} responses",
+                    response_counts.perimeterx_challenges + response_counts.perimeterx_blocks,
+                    response_counts.total()
+                ),
+                false,
+            );
+        }
+        debug!("Response counts: {:?}", response_counts);
+        let pages_failed = component_scraper.pages_failed();
+        drop(component_scraper);
+        let data_manager = DataManager::new(self.args.clone());
+        let saved = match output_format {
+            OutputFormat::Ndjson => {
+                // Every page has been written already; only the failed pages came back, and those
+                // were reported by the component scraper.
+                components?;
+                let ndjson_writer = ndjson_writer.and_then(Arc::into_inner).ok_or_else(|| {
+                    anyhow!("The NDJSON writer of {} is still in use", job.category_name)
+                })?;
+                data_manager
+                    .save_ndjson_to_disk(ndjson_writer, octopart_metadata, scraper_time)
+                    .await?
+            }
+            OutputFormat::Sqlite => {
+                data_manager
+                    .save_to_database(&job.category_name, components, scraper_time)
+                    .await?
+            }
+            OutputFormat::Csv => {
+                data_manager
+                    .save_csv_to_disk(&filename, components, scraper_time)
+                    .await?
+            }
+            OutputFormat::Json => {
+                data_manager
+                    .save_to_disk(&filename, components, &mut octopart_metadata, scraper_time)
+                    .await?
+            }
+        };
+        if let Some(saved) = &saved {
+            debug!("Saved components to {}", saved.filepath.display());
+        }
+
+        // 6. Once the output is safe on disk the checkpoint is no longer needed, unless pages are
+        // missing from it that `--resume` can still fetch.
+        if pages_failed > 0 {
+            print_info_message(
+                &format!(
+                    "Keeping the checkpoint of {}; run again with --resume to fetch the {} failed \
+                     page(s).",
+                    job.category_name, pages_failed
+                ),
+                false,
+            );
+        } else if let (true, Some(checkpoint)) = (saved.is_some(), Arc::into_inner(checkpoint)) {
+            checkpoint.remove().await?;
+        }
+
+        Ok(saved)
+    }
+
+    /// Fetches the attribute buckets of a job and counts the components of each combination.
+    async fn count_components(
+        &self,
+        job: &Arc<ScrapeJob>,
+        request_sender: &Arc<RequestSender>,
+        response_handler: &Arc<ResponseHandler>,
+    ) -> Result<AttributeBucketCombinations> {
+        // 1. Get the attribute ids from the job.
+        let attribute_shortnames = &job.attribute_shortnames;
         debug!("Attribute Shortnames: {:?}", attribute_shortnames);
 
         // 2. Get the attribute buckets from the attribute scraper.
         let attribute_scraper = AttributeScraper::new(
             self.args.clone(),
+            job.clone(),
             request_sender.clone(),
             response_handler.clone(),
         );
@@ -53,35 +301,16 @@ impl BatchManager {
         // 3. Get the filter combination & component counts from the component counter.
         let mut component_counter = ComponentCounter::new(
             self.args.clone(),
+            job.clone(),
             self.batch_size,
             attribute_shortnames.clone(),
             attribute_buckets,
             request_sender.clone(),
             response_handler.clone(),
-        )
-        .expect("Failed to create component counter");
+        )?;
         let component_counts = component_counter.process().await?;
         debug!("Component Counts: {:?}", component_counts);
 
-        // 4. Get the components from the component scraper.
-        let mut component_scraper = ComponentScraper::new(
-            self.args.clone(),
-            self.batch_size,
-            request_sender.clone(),
-            response_handler.clone(),
-        );
-        let components = component_scraper.process(component_counts).await;
-        debug!("Components: {:?}", components);
-
-        // 5. Save the components to disk.
-        let mut octopart_metadata = component_scraper.get_octopart_component_metadata();
-        let scraper_time = component_scraper.get_scraper_component_metadata(start.elapsed());
-        let data_manager = DataManager::new(self.args.clone());
-        data_manager
-            .save_to_disk(components, &mut octopart_metadata, scraper_time)
-            .await?;
-        debug!("Saved components to disk");
-
-        Ok(())
+        Ok(component_counts)
     }
 }
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use serde_json::{json, Map, Value};
+use tokio::sync::RwLock;
+
+use crate::batch_manager::job::ScrapeJob;
+use crate::cli::Arguments;
+use crate::config::constants::OCTOPART_COMPONENT_COMBINATION_LIMIT;
+use crate::error::ScraperError;
+
+use super::models::SearchResult;
+use super::request_sender::RequestSender;
+use super::response_handler::ResponseHandler;
+use super::transport::Transport;
+
+/// The mica capacitor parts served by `FakeTransport::mica_capacitors`.
+pub(crate) const MICA_CAPACITORS_FIXTURE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/mica_capacitors.json"
+));
+
+/// An in-memory stand-in for Octopart that answers `FilterModalSearch` and `PricesViewSearch`
+/// payloads from a fixed list of parts.
+///
+/// Parts use the shape of `part` in `PART_SEARCH_QUERY`. A spec may carry a `float_value`, which
+/// is reported in bucket aggregations and matched by filters the way Octopart does for numeric
+/// attributes; it is stripped from the parts returned in search results.
+pub(crate) struct FakeTransport {
+    parts: Vec<Value>,
+    requests: Mutex<Vec<Value>>,
+    failing_starts: Vec<usize>,
+}
+
+impl FakeTransport {
+    pub(crate) fn new(parts: Vec<Value>) -> Self {
+        Self {
+            parts,
+            requests: Mutex::new(Vec::new()),
+            failing_starts: Vec::new(),
+        }
+    }
+
+    /// Answers every component search starting at one of `failing_starts` with a truncated
+    /// response, the way a connection dropped mid-response would.
+    pub(crate) fn with_failing_starts(mut self, failing_starts: &[usize]) -> Self {
+        self.failing_starts = failing_starts.to_vec();
+        self
+    }
+
+    /// A fake serving the parts in `tests/fixtures/mica_capacitors.json`.
+    pub(crate) fn mica_capacitors() -> Self {
+        Self::new(serde_json::from_str(MICA_CAPACITORS_FIXTURE).unwrap())
+    }
+
+    /// Every payload received so far, in order.
+    pub(crate) fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn matching_parts(&self, filters: Option<&Map<String, Value>>) -> Vec<&Value> {
+        self.parts
+            .iter()
+            .filter(|part| {
+                filters.into_iter().flatten().all(|(key, values)| {
+                    let values = values.as_array().cloned().unwrap_or_default();
+                    let value = if key == "category_id" {
+                        part.pointer("/category/id").cloned()
+                    } else {
+                        spec(part, key).map(|spec| json!(spec_filter_value(spec)))
+                    };
+                    value.is_some_and(|value| values.contains(&value))
+                })
+            })
+            .collect()
+    }
+
+    fn filter_modal_search(&self, variables: &Value) -> Value {
+        let parts = self.matching_parts(variables["filters"].as_object());
+        let spec_aggs: Vec<_> = variables["attribute_names"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|attribute_name| {
+                let shortname = attribute_name.as_str().unwrap_or_default();
+                let mut buckets: BTreeMap<String, (usize, Option<f64>)> = BTreeMap::new();
+                for spec in parts.iter().filter_map(|part| spec(part, shortname)) {
+                    let display_value = spec["display_value"].as_str().unwrap_or_default();
+                    let bucket = buckets
+                        .entry(display_value.to_string())
+                        .or_insert((0, spec["float_value"].as_f64()));
+                    bucket.0 += 1;
+                }
+                let buckets: Vec<_> = buckets
+                    .into_iter()
+                    .map(|(display_value, (count, float_value))| {
+                        json!({
+                            "count": count,
+                            "display_value": display_value,
+                            "float_value": float_value,
+                        })
+                    })
+                    .collect();
+                json!({ "buckets": buckets })
+            })
+            .collect();
+
+        json!({
+            "data": {
+                "search": {
+                    "hits": parts.len(),
+                    "spec_aggs": spec_aggs,
+                }
+            }
+        })
+    }
+
+    fn prices_view_search(&self, variables: &Value) -> Value {
+        let mut parts = self.matching_parts(variables["filters"].as_object());
+        if let Some(sort) = variables["sort"].as_str() {
+            parts.sort_by_key(|part| part[sort].to_string());
+            if variables["sort_dir"] == "desc" {
+                parts.reverse();
+            }
+        }
+
+        // Like Octopart, only the first results of a search can be paged through.
+        let hits = parts.len();
+        let reachable = hits.min(OCTOPART_COMPONENT_COMBINATION_LIMIT);
+        let start = variables["start"].as_u64().unwrap_or(0) as usize;
+        let limit = variables["limit"].as_u64().unwrap_or(10) as usize;
+        let results: Vec<_> = parts[start.min(reachable)..(start + limit).min(reachable)]
+            .iter()
+            .map(|part| {
+                let mut part = (*part).clone();
+                if let Some(specs) = part["specs"].as_array_mut() {
+                    for spec in specs {
+                        if let Some(spec) = spec.as_object_mut() {
+                            spec.remove("float_value");
+                        }
+                    }
+                }
+                json!({
+                    "_cache_id": part["_cache_id"],
+                    "description": part.pointer("/descriptions/0/text"),
+                    "part": part,
+                })
+            })
+            .collect();
+
+        json!({
+            "data": {
+                "search": {
+                    "applied_category": {
+                        "ancestors": [],
+                        "id": variables.pointer("/filters/category_id/0"),
+                        "name": "Fake Category",
+                        "path": "/fake-category",
+                    },
+                    "applied_filters": [],
+                    "results": results,
+                    "hits": hits,
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    async fn post(&self, _headers: HeaderMap, body: &Value) -> Result<String, ScraperError> {
+        self.requests.lock().unwrap().push(body.clone());
+        let response = match body["operationName"].as_str() {
+            Some("FilterModalSearch") => self.filter_modal_search(&body["variables"]),
+            Some("PricesViewSearch") => {
+                let start = body["variables"]["start"].as_u64().unwrap_or(0) as usize;
+                if self.failing_starts.contains(&start) {
+                    return Ok(r#"{"data": {"search": "#.to_string());
+                }
+                self.prices_view_search(&body["variables"])
+            }
+            _ => return Err(ScraperError::HttpStatus(400)),
+        };
+        Ok(response.to_string())
+    }
+}
+
+/// Builds a part with the given id and `(shortname, display_value, float_value)` specs.
+pub(crate) fn fake_part(
+    id: usize,
+    category_id: &str,
+    specs: &[(&str, &str, Option<f64>)],
+) -> Value {
+    let specs: Vec<_> = specs
+        .iter()
+        .map(|(shortname, display_value, float_value)| {
+            json!({
+                "attribute": { "id": shortname, "name": shortname, "shortname": shortname },
+                "display_value": display_value,
+                "float_value": float_value,
+            })
+        })
+        .collect();
+    json!({
+        "_cache_id": format!("part-{}", id),
+        "category": { "id": category_id },
+        "id": id.to_string(),
+        "manufacturer": { "id": "1", "is_verified": true, "name": "Fake Manufacturer" },
+        "median_price_1000": null,
+        "mpn": format!("FAKE-{:06}", id),
+        "specs": specs,
+    })
+}
+
+/// Builds a search result the way Octopart serves it, for tests that work on components without
+/// fetching them.
+///
+/// A component starts out made by Kemet, without category, price, description, datasheet or
+/// specs.
+pub(crate) struct FakeComponent {
+    component: Value,
+}
+
+impl FakeComponent {
+    pub(crate) fn new(id: &str, mpn: &str) -> Self {
+        Self {
+            component: json!({
+                "_cache_id": null,
+                "description": null,
+                "part": {
+                    "id": id,
+                    "manufacturer": { "id": "196", "name": "Kemet" },
+                    "median_price_1000": null,
+                    "mpn": mpn,
+                    "specs": [],
+                },
+            }),
+        }
+    }
+
+    pub(crate) fn with_category(mut self, category_id: &str) -> Self {
+        self.component["part"]["category"] = json!({ "id": category_id });
+        self
+    }
+
+    pub(crate) fn with_manufacturer(mut self, name: &str) -> Self {
+        self.component["part"]["manufacturer"]["name"] = json!(name);
+        self
+    }
+
+    /// Sets the median price of 1000 pieces, in USD.
+    pub(crate) fn with_price(mut self, price: f64) -> Self {
+        self.component["part"]["median_price_1000"] =
+            json!({ "converted_currency": "USD", "converted_price": price });
+        self
+    }
+
+    pub(crate) fn with_description(mut self, description: &str) -> Self {
+        self.component["description"] = json!(description);
+        self
+    }
+
+    pub(crate) fn with_datasheet(mut self, url: &str) -> Self {
+        self.component["part"]["best_datasheet"] = json!({ "url": url });
+        self
+    }
+
+    /// Sets the `(shortname, display_value)` specs; the shortname doubles as attribute id and
+    /// name.
+    pub(crate) fn with_specs(mut self, specs: &[(&str, &str)]) -> Self {
+        self.component["part"]["specs"] = specs
+            .iter()
+            .map(|(shortname, display_value)| {
+                json!({
+                    "attribute": { "id": shortname, "name": shortname, "shortname": shortname },
+                    "display_value": display_value,
+                })
+            })
+            .collect();
+        self
+    }
+
+    /// The component as JSON, e.g. to write an output file.
+    pub(crate) fn into_value(self) -> Value {
+        self.component
+    }
+
+    pub(crate) fn build(self) -> SearchResult {
+        serde_json::from_value(self.component).unwrap()
+    }
+}
+
+/// The shared state every fetcher needs, wired to a `FakeTransport`.
+pub(crate) struct FakeContext {
+    pub(crate) args: Arc<RwLock<Arguments>>,
+    pub(crate) job: Arc<ScrapeJob>,
+    pub(crate) transport: Arc<FakeTransport>,
+    pub(crate) request_sender: Arc<RequestSender>,
+    pub(crate) response_handler: Arc<ResponseHandler>,
+}
+
+impl FakeContext {
+    pub(crate) fn new(transport: FakeTransport, job: ScrapeJob) -> Self {
+        let transport = Arc::new(transport);
+        Self {
+            args: Arc::new(RwLock::new(Arguments::default())),
+            job: Arc::new(job),
+            request_sender: Arc::new(RequestSender::with_transport(transport.clone())),
+            transport,
+            response_handler: Arc::new(ResponseHandler::new()),
+        }
+    }
+}
+
+fn spec<'a>(part: &'a Value, shortname: &str) -> Option<&'a Value> {
+    part["specs"]
+        .as_array()?
+        .iter()
+        .find(|spec| spec.pointer("/attribute/shortname") == Some(&json!(shortname)))
+}
+
+/// The value a filter has to contain to match the spec, mirroring `AttributeBucket::filter_value`.
+fn spec_filter_value(spec: &Value) -> String {
+    match spec["float_value"].as_f64() {
+        Some(float_value) => float_value.to_string(),
+        None => spec["display_value"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
@@ -1,2 +1,9 @@
+pub(crate) mod models;
+pub(crate) mod rate_limiter;
 pub(crate) mod request_sender;
 pub(crate) mod response_handler;
+pub(crate) mod session_pool;
+pub(crate) mod transport;
+
+#[cfg(test)]
+pub(crate) mod fake_transport;
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The envelope of every GraphQL response: `{"data": {"search": ...}}`.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SearchResponse<S> {
+    pub(crate) data: SearchData<S>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SearchData<S> {
+    pub(crate) search: S,
+}
+
+/// The `search` of a `FilterModalSearch` (`ATTRIBUTE_BUCKET_QUERY`) response.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct AttributeSearch {
+    pub(crate) hits: usize,
+    /// One aggregation per requested attribute, in the order of `attribute_names`.
+    #[serde(default)]
+    pub(crate) spec_aggs: Vec<SpecAgg>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SpecAgg {
+    #[serde(default)]
+    pub(crate) buckets: Vec<Bucket>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Bucket {
+    pub(crate) count: usize,
+    pub(crate) display_value: String,
+    /// Only set for numeric attributes.
+    pub(crate) float_value: Option<f64>,
+}
+
+/// The `search` of a `PricesViewSearch` (`PART_SEARCH_QUERY`) response.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct PartSearch {
+    pub(crate) applied_category: Option<AppliedCategory>,
+    #[serde(default)]
+    pub(crate) applied_filters: Vec<AppliedFilter>,
+    #[serde(default)]
+    pub(crate) results: Vec<SearchResult>,
+    pub(crate) hits: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct AppliedCategory {
+    #[serde(default)]
+    pub(crate) ancestors: Vec<Category>,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Category {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct AppliedFilter {
+    #[serde(default)]
+    pub(crate) display_values: Vec<String>,
+    pub(crate) name: String,
+    pub(crate) shortname: String,
+    #[serde(default)]
+    pub(crate) values: Vec<String>,
+}
+
+/// One entry of `results`: a part and the search's description of it.
+///
+/// This is the component the scraper collects and saves.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct SearchResult {
+    #[serde(rename = "_cache_id")]
+    pub(crate) cache_id: Option<String>,
+    pub(crate) description: Option<String>,
+    pub(crate) part: Part,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Part {
+    #[serde(rename = "_cache_id")]
+    pub(crate) cache_id: Option<String>,
+    pub(crate) best_datasheet: Option<Document>,
+    pub(crate) best_image: Option<Document>,
+    pub(crate) category: Option<PartCategory>,
+    /// A free-form JSON scalar in the Octopart schema.
+    #[serde(default)]
+    pub(crate) counts: Value,
+    #[serde(default)]
+    pub(crate) descriptions: Vec<Description>,
+    pub(crate) id: String,
+    pub(crate) manufacturer: Manufacturer,
+    pub(crate) manufacturer_url: Option<String>,
+    pub(crate) median_price_1000: Option<Price>,
+    pub(crate) mpn: String,
+    #[serde(default)]
+    pub(crate) specs: Vec<Spec>,
+}
+
+impl Part {
+    /// The key a part is deduplicated by.
+    pub(crate) fn key(&self) -> PartKey {
+        if self.id.is_empty() {
+            PartKey::MpnManufacturer(self.mpn.clone(), self.manufacturer.name.clone())
+        } else {
+            PartKey::Id(self.id.clone())
+        }
+    }
+}
+
+/// What identifies a part across pages: its Octopart id, or its MPN and manufacturer when a
+/// response carries no id.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum PartKey {
+    Id(String),
+    MpnManufacturer(String, String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Document {
+    pub(crate) url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct PartCategory {
+    pub(crate) id: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Description {
+    pub(crate) text: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Manufacturer {
+    pub(crate) id: String,
+    pub(crate) is_verified: Option<bool>,
+    pub(crate) name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Price {
+    #[serde(rename = "_cache_id")]
+    pub(crate) cache_id: Option<String>,
+    pub(crate) converted_currency: Option<String>,
+    pub(crate) converted_price: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Spec {
+    pub(crate) attribute: Attribute,
+    pub(crate) display_value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Attribute {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) shortname: String,
+}
+
+/// Lists the places where `typed`, a response re-serialized from its models, differs in shape from
+/// the `original` JSON it was read from.
+///
+/// Fields the models do not know about are dropped when deserializing, and fields Octopart left
+/// out come back as `null` or `[]`, so both show up as a difference.
+///
+/// # Arguments
+/// * `original` - The JSON as received.
+/// * `typed` - The same response after a round trip through the models.
+/// * `path` - The JSON pointer of both values, `""` for the root.
+/// * `differences` - Receives one description per difference.
+pub(crate) fn shape_differences(
+    original: &Value,
+    typed: &Value,
+    path: &str,
+    differences: &mut Vec<String>,
+) {
+    match (original, typed) {
+        (Value::Object(original), Value::Object(typed)) => {
+            for (key, value) in original {
+                let field_path = format!("{}/{}", path, key);
+                match typed.get(key) {
+                    Some(typed_value) => {
+                        shape_differences(value, typed_value, &field_path, differences)
+                    }
+                    None => differences.push(format!("unexpected field {}", field_path)),
+                }
+            }
+            for key in typed.keys().filter(|key| !original.contains_key(*key)) {
+                differences.push(format!("missing field {}/{}", path, key));
+            }
+        }
+        (Value::Array(original), Value::Array(typed)) => {
+            for (index, (value, typed_value)) in original.iter().zip(typed).enumerate() {
+                shape_differences(
+                    value,
+                    typed_value,
+                    &format!("{}/{}", path, index),
+                    differences,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{shape_differences, SearchResult};
+
+    #[test]
+    fn reports_unexpected_and_missing_fields() {
+        let original = json!({
+            "_cache_id": "result-1",
+            "description": "Mica capacitor",
+            "part": {
+                "id": "1",
+                "mpn": "MC001",
+                "manufacturer": { "id": "196", "is_verified": true, "name": "Kemet" },
+                "lifecycle_status": "Active",
+            },
+        });
+        let result: SearchResult = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(result.part.manufacturer.name, "Kemet");
+        assert!(result.part.specs.is_empty());
+
+        let mut differences = Vec::new();
+        let typed = serde_json::to_value(&result).unwrap();
+        shape_differences(&original, &typed, "", &mut differences);
+        assert!(differences.contains(&"unexpected field /part/lifecycle_status".to_string()));
+        assert!(differences.contains(&"missing field /part/median_price_1000".to_string()));
+        assert!(!differences
+            .iter()
+            .any(|difference| difference.contains("/part/manufacturer/")));
+    }
+}
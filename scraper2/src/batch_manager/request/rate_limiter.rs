@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
+
+/// A token bucket that paces requests to a steady rate while allowing short bursts.
+///
+/// The bucket holds up to `burst` tokens and refills at `requests_per_second`; every request
+/// takes one token and waits for a refill when the bucket is empty. A single `RateLimiter` is
+/// shared by every `TaskProcessor` through the `RequestSender`.
+pub(crate) struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Mutex<TokenBucket>,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter with a full bucket.
+    ///
+    /// # Arguments
+    /// * `requests_per_second` - The sustained number of requests allowed per second.
+    /// * `burst` - The number of requests that may be sent back to back after a pause.
+    ///
+    /// # Returns
+    /// A `Result` with the `RateLimiter`, or an error if either value is not positive.
+    pub(crate) fn new(requests_per_second: f64, burst: u32) -> Result<Self> {
+        if !(requests_per_second > 0.0 && requests_per_second.is_finite()) {
+            bail!(
+                "The request rate must be a positive number, got {}",
+                requests_per_second
+            );
+        }
+        if burst == 0 {
+            bail!("The request burst must be at least 1");
+        }
+        Ok(Self {
+            requests_per_second,
+            burst: burst as f64,
+            bucket: Mutex::new(TokenBucket {
+                tokens: burst as f64,
+                last_refill: Instant::now(),
+            }),
+        })
+    }
+
+    /// Waits until a request may be sent and takes a token for it.
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens =
+                    (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second)
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::RateLimiter;
+
+    #[tokio::test]
+    async fn allows_a_burst_then_paces_requests() {
+        let rate_limiter = RateLimiter::new(50.0, 2).unwrap();
+
+        let start = Instant::now();
+        rate_limiter.acquire().await;
+        rate_limiter.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(15));
+
+        // Three more tokens at 50 per second take at least 60ms to refill.
+        for _ in 0..3 {
+            rate_limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(55));
+    }
+
+    #[test]
+    fn rejects_non_positive_settings() {
+        assert!(RateLimiter::new(0.0, 1).is_err());
+        assert!(RateLimiter::new(f64::NAN, 1).is_err());
+        assert!(RateLimiter::new(1.0, 0).is_err());
+    }
+}
@@ -1,25 +1,36 @@
 use std::collections::HashMap;
-use std::io::Error;
+use std::sync::Arc;
 use std::time::Duration;
 
 use log::debug;
-use reqwest::{header, Client};
+use rand::Rng;
+use reqwest::header;
 use serde_json::{json, Map, Value};
 
+use crate::batch_manager::job::ScrapeJob;
+use crate::batch_manager::types::ComponentSort;
 use crate::cli::Arguments;
-use crate::config::categories::{ATTRIBUTES_MAP, CATEGORIES_MAP};
-use crate::config::constants::ENDPOINT;
+use crate::config::constants::{
+    MAX_REQUEST_RETRIES, REQUEST_BACKOFF_BASE_DELAY, REQUEST_BACKOFF_MAX_DELAY,
+};
 use crate::config::queries::{ATTRIBUTE_BUCKET_QUERY, PART_SEARCH_QUERY};
+use crate::error::ScraperError;
+
+use super::rate_limiter::RateLimiter;
+use super::response_handler::ResponseHandler;
+use super::session_pool::SessionPool;
+use super::transport::Transport;
 
 /// Enumerates different types of requests that can be handled.
 pub(crate) enum RequestType {
     /// Request for attributes.
     Attributes,
-    /// Request for parts, with filters and pagination options.
+    /// Request for parts, with filters, pagination and sorting options.
     Components {
         filters: HashMap<String, Vec<String>>,
         start: usize,
         end: usize,
+        sort: Option<ComponentSort>,
     },
     /// Request for counting components with optional attributes and filters.
     ComponentCount {
@@ -29,128 +40,86 @@ pub(crate) enum RequestType {
 }
 
 /// Manages the sending of different types of requests to a remote endpoint.
+///
+/// A single `RequestSender` (and therefore a single HTTP session) is shared by every `ScrapeJob`
+/// of a run; the category and attributes are passed along with each request.
+///
+/// Requests are paced by an optional `RateLimiter`, and requests that fail transiently are
+/// retried with jittered exponential backoff before the failure is returned. Requests are spread
+/// across the sessions of the `SessionPool`, falling back to `--px` once the pool is used up.
 pub(crate) struct RequestSender {
-    client: Client,
-    pub(crate) category_name: Option<String>,
-    category_id: Option<String>,
-    attribute_shortnames: Option<Vec<String>>,
-    pub(crate) attribute_names: Option<Vec<String>>,
+    transport: Arc<dyn Transport>,
+    rate_limiter: Option<RateLimiter>,
+    session_pool: SessionPool,
 }
 
 impl RequestSender {
-    /// Creates a new instance of `RequestSender` with initial configuration from the provided arguments.
-    pub(crate) fn new(args: &Arguments) -> Self {
-        let client = Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .connection_verbose(true)
-            .http1_only()
-            .http1_title_case_headers()
-            .cookie_store(true)
-            .timeout(Duration::from_secs(10))
-            .build()
-            .unwrap();
-        let category_name = args.category_name.clone();
-        let category_id = RequestSender::parse_category(args).ok();
-        let attribute_names = args.attribute_names.clone();
-        let attribute_shortnames = RequestSender::parse_attributes(args).ok();
+    /// Creates a new instance of `RequestSender` that sends every request through `transport`.
+    pub(crate) fn with_transport(transport: Arc<dyn Transport>) -> Self {
         Self {
-            client,
-            category_name,
-            category_id,
-            attribute_names,
-            attribute_shortnames,
+            transport,
+            rate_limiter: None,
+            session_pool: SessionPool::default(),
         }
     }
 
-    // Getter methods
-
-    #[allow(dead_code)]
-    pub(crate) fn get_category_name(&self) -> Option<&String> {
-        self.category_name.as_ref()
-    }
-
-    #[allow(dead_code)]
-    pub(crate) fn get_attribute_names(&self) -> Option<&Vec<String>> {
-        self.attribute_names.as_ref()
+    /// Paces every request sent from now on through `rate_limiter`.
+    pub(crate) fn with_rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter),
+            ..self
+        }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn get_category_id(&self) -> Option<&String> {
-        self.category_id.as_ref()
+    /// Spreads every request sent from now on across the sessions of `session_pool`.
+    pub(crate) fn with_session_pool(self, session_pool: SessionPool) -> Self {
+        Self {
+            session_pool,
+            ..self
+        }
     }
 
-    pub(crate) fn get_attribute_shortnames(&self) -> Option<&Vec<String>> {
-        self.attribute_shortnames.as_ref()
+    /// A short description of the session pool's health, if a pool is in use.
+    pub(crate) fn session_health_message(&self) -> Option<String> {
+        self.session_pool.health_message()
     }
 
-    /// Parses HTTP headers from the given arguments for constructing a request.
+    /// Parses HTTP headers from a session's PX key and user agent for constructing a request.
     ///
     /// # Arguments
-    /// * `args` - Application arguments containing potential headers like cookies and user agent.
+    /// * `px` - The `_px` cookie value, if any.
+    /// * `user_agent` - The user agent, if any.
     ///
     /// # Returns
-    /// A `Result` containing the `HeaderMap` on success, or an `Error` if header parsing fails.
-    fn parse_headers(&self, args: &Arguments) -> Result<header::HeaderMap, Error> {
+    /// A `Result` containing the `HeaderMap` on success, or a `ScraperError::InvalidHeader` if
+    /// the PX key or user agent cannot be sent as a header.
+    fn parse_headers(
+        px: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<header::HeaderMap, ScraperError> {
         let mut headers = header::HeaderMap::new();
-        if let Some(px) = &args.px {
+        if let Some(px) = px {
             headers.insert(
                 reqwest::header::COOKIE,
-                header::HeaderValue::from_str(&format!("_px={}", px.as_str()))
-                    .expect("Failed to parse px header"),
+                header::HeaderValue::from_str(&format!("_px={}", px))
+                    .map_err(|_| ScraperError::InvalidHeader("PX key"))?,
             );
         }
 
-        if let Some(user_agent) = &args.user_agent {
+        if let Some(user_agent) = user_agent {
             headers.insert(
                 reqwest::header::USER_AGENT,
-                header::HeaderValue::from_str(user_agent.as_str())
-                    .expect("Failed to parse user agent header"),
+                header::HeaderValue::from_str(user_agent)
+                    .map_err(|_| ScraperError::InvalidHeader("user agent"))?,
             );
         }
         Ok(headers)
     }
 
-    /// Determines the category ID from the provided arguments.
-    ///
-    /// # Arguments
-    /// * `args` - Application arguments containing the category name.
-    ///
-    /// # Returns
-    /// A `Result` with the parsed category ID as `String` on success, or an `Error` if the category is not found.
-    fn parse_category(args: &Arguments) -> Result<String, Error> {
-        match &args.category_name {
-            Some(category_name) => Ok((*CATEGORIES_MAP.get(category_name).unwrap()).to_string()),
-            None => Err(Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Category name is required",
-            )),
-        }
-    }
-
-    /// Retrieves a list of attribute IDs based on the provided argument names.
-    ///
-    /// # Arguments
-    /// * `args` - Application arguments containing attribute names.
-    ///
-    /// # Returns
-    /// A `Result` with a `Vec<String>` of attribute IDs on success, or an `Error` if any attribute name is not found.
-    fn parse_attributes(args: &Arguments) -> Result<Vec<String>, Error> {
-        match &args.attribute_names {
-            Some(attribute_names) => Ok(attribute_names
-                .to_vec()
-                .iter()
-                .map(|attribute_name| (*ATTRIBUTES_MAP.get(attribute_name).unwrap()).to_string())
-                .collect()),
-            None => Err(Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Attribute names are required",
-            )),
-        }
-    }
-
     /// Constructs the payload for the 'ComponentCount' request with optional attributes and filters.
     ///
     /// # Arguments
+    /// * `job` - The job whose category is searched.
     /// * `attribute_names` - Optional list of attribute names to include in the request.
     /// * `filters` - Optional hashmap of filters to apply in the request.
     ///
@@ -158,13 +127,14 @@ impl RequestSender {
     /// A `Value` representing the JSON payload for the request.
     pub(crate) fn get_component_count_payload(
         &self,
+        job: &ScrapeJob,
         attribute_names: Option<Vec<String>>,
         filters: Option<HashMap<String, Vec<String>>>,
     ) -> Value {
         // If attribute is Some, then we want to put it into a Vec, otherwise an empty Vec
         let filter_map = filters.unwrap_or_default();
         let mut filters = Map::new();
-        filters.insert("category_id".to_string(), json!([self.category_id]));
+        filters.insert("category_id".to_string(), json!([job.category_id]));
         filter_map.iter().for_each(|(k, v)| {
             filters.insert(k.to_string(), json!(v));
         });
@@ -181,18 +151,21 @@ impl RequestSender {
         json_data
     }
 
-    /// Builds the payload for an 'Attributes' request using configured attributes in the instance.
+    /// Builds the payload for an 'Attributes' request using the attributes of the given job.
+    ///
+    /// # Arguments
+    /// * `job` - The job whose category and attributes are requested.
     ///
     /// # Returns
     /// A `Value` representing the JSON payload for the request.
-    fn get_attributes_payload(&self) -> Value {
+    fn get_attributes_payload(&self, job: &ScrapeJob) -> Value {
         let json_data = json!({
             "operationName": "FilterModalSearch",
             "variables": {
-                "attribute_names": self.attribute_shortnames,
+                "attribute_names": job.attribute_shortnames,
                 "currency": "USD",
                 "filters": {
-                    "category_id": [self.category_id],
+                    "category_id": [job.category_id],
                 },
                 "in_stock_only": false,
             },
@@ -204,25 +177,29 @@ impl RequestSender {
     /// Prepares the payload for a 'Parts' request with specified filters and pagination.
     ///
     /// # Arguments
+    /// * `job` - The job whose category is searched.
     /// * `filters` - A hashmap of filters to apply in the request.
     /// * `start` - The starting index for pagination.
     /// * `end` - The ending index for pagination.
+    /// * `sort` - Optional sort key and direction; Octopart's relevance order is used otherwise.
     ///
     /// # Returns
     /// A `Value` representing the JSON payload for the request.
     fn get_components_payload(
         &self,
+        job: &ScrapeJob,
         filters: HashMap<String, Vec<String>>,
         start: usize,
         end: usize,
+        sort: Option<ComponentSort>,
     ) -> Value {
         let filter_map = filters;
         let mut filters = Map::new();
-        filters.insert("category_id".to_string(), json!([self.category_id]));
+        filters.insert("category_id".to_string(), json!([job.category_id]));
         filter_map.iter().for_each(|(k, v)| {
             filters.insert(k.to_string(), json!(v));
         });
-        let json_data = json!({
+        let mut json_data = json!({
             "operationName": "PricesViewSearch",
             "variables": {
                 "country": "US",
@@ -234,62 +211,259 @@ impl RequestSender {
             },
             "query": PART_SEARCH_QUERY.to_string(),
         });
+        if let Some(sort) = sort {
+            json_data["variables"]["sort"] = json!(sort.key);
+            json_data["variables"]["sort_dir"] = json!(sort.direction.as_str());
+        }
         json_data
     }
 
     /// Sends a request to the server based on the specified `RequestType` and provided arguments.
     ///
+    /// A session of the pool that gets blocked is retired and the request is sent again with the
+    /// next one; `ScraperError::Blocked` is only returned when the `--px` key is blocked too.
+    ///
     /// # Arguments
     /// * `args` - Application arguments to be used for the request.
+    /// * `job` - The job the request belongs to.
     /// * `request_type` - The type of request to send (`Attributes`, `Parts`, `ComponentCount`).
+    /// * `response_handler` - Classifies the response of the job's requests.
     ///
     /// # Returns
-    /// A `Result` containing the server response as a `Value` on success, or an `Error` if the request fails.
+    /// A `Result` containing the server response as a `Value` on success, or a `ScraperError`
+    /// if the request failed or `ResponseHandler::classify_response` rejected the response.
     pub(crate) async fn send_request(
         &self,
         args: &Arguments,
+        job: &ScrapeJob,
         request_type: RequestType,
-    ) -> Result<Value, Error> {
-        let headers = self.parse_headers(args)?;
+        response_handler: &ResponseHandler,
+    ) -> Result<Value, ScraperError> {
         let body = match request_type {
-            RequestType::Attributes => self.get_attributes_payload(),
+            RequestType::Attributes => self.get_attributes_payload(job),
             RequestType::Components {
                 filters,
                 start,
                 end,
-            } => self.get_components_payload(filters, start, end),
+                sort,
+            } => self.get_components_payload(job, filters, start, end, sort),
             RequestType::ComponentCount {
                 attributes,
                 filters,
             } => {
-                let attributes = match attributes {
-                    Some(attributes) => Some(attributes),
-                    None => self.attribute_shortnames.clone(),
-                };
-                self.get_component_count_payload(attributes, filters)
+                let attributes = attributes.unwrap_or_else(|| job.attribute_shortnames.clone());
+                self.get_component_count_payload(job, Some(attributes), filters)
             }
         };
-        let response = self
-            .client
-            .post(ENDPOINT)
-            .headers(headers)
-            .json(&body)
-            .send()
+
+        loop {
+            let (session_index, px, user_agent) = match self.session_pool.next_session() {
+                Some((index, session)) => (
+                    Some(index),
+                    Some(session.px.as_str()),
+                    session.user_agent.as_deref().or(args.user_agent.as_deref()),
+                ),
+                None => (None, args.px.as_deref(), args.user_agent.as_deref()),
+            };
+            let headers = Self::parse_headers(px, user_agent)?;
+            let raw_response = self.post_with_retries(headers, &body).await?;
+            match (
+                response_handler.classify_response(&raw_response),
+                session_index,
+            ) {
+                (Err(ScraperError::Blocked), Some(index)) => self.session_pool.retire(index),
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Posts `body`, retrying transient failures up to `MAX_REQUEST_RETRIES` times.
+    async fn post_with_retries(
+        &self,
+        headers: header::HeaderMap,
+        body: &Value,
+    ) -> Result<String, ScraperError> {
+        let mut retries = 0;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            match self.transport.post(headers.clone(), body).await {
+                Err(error) if error.is_transient() && retries < MAX_REQUEST_RETRIES => {
+                    let delay = Self::backoff_delay(retries);
+                    debug!("Retrying in {:?} after transient error: {}", delay, error);
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Picks a random delay of up to `REQUEST_BACKOFF_BASE_DELAY * 2^retries`, capped at
+    /// `REQUEST_BACKOFF_MAX_DELAY`, so that requests failing together do not retry together.
+    fn backoff_delay(retries: u32) -> Duration {
+        let max_delay = REQUEST_BACKOFF_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(retries))
+            .min(REQUEST_BACKOFF_MAX_DELAY);
+        max_delay.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use reqwest::header::{HeaderMap, COOKIE};
+    use serde_json::Value;
+
+    use super::{RequestSender, RequestType};
+    use crate::batch_manager::job::ScrapeJob;
+    use crate::batch_manager::request::response_handler::ResponseHandler;
+    use crate::batch_manager::request::session_pool::{Session, SessionPool};
+    use crate::batch_manager::request::transport::Transport;
+    use crate::cli::Arguments;
+    use crate::error::ScraperError;
+
+    const SEARCH_RESPONSE: &str = r#"{"data":{"search":{"hits":1}}}"#;
+    const BLOCK_RESPONSE: &str = r#"{"appId":"PXabc123","uuid":"1","blockScript":"/captcha.js"}"#;
+
+    /// Fails the first `failures` requests with `error()`, then answers with `body`.
+    struct FlakyTransport {
+        failures: usize,
+        error: fn() -> ScraperError,
+        body: &'static str,
+        requests: AtomicUsize,
+    }
+
+    impl FlakyTransport {
+        fn new(failures: usize, error: fn() -> ScraperError, body: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                failures,
+                error,
+                body,
+                requests: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Transport for FlakyTransport {
+        async fn post(&self, _headers: HeaderMap, _body: &Value) -> Result<String, ScraperError> {
+            if self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err((self.error)());
+            }
+            Ok(self.body.to_string())
+        }
+    }
+
+    /// Blocks every request whose `_px` cookie is in `blocked`, recording the cookies it saw.
+    struct SessionTransport {
+        blocked: Vec<&'static str>,
+        cookies: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Transport for SessionTransport {
+        async fn post(&self, headers: HeaderMap, _body: &Value) -> Result<String, ScraperError> {
+            let cookie = headers[COOKIE].to_str().unwrap().to_string();
+            let blocked = self
+                .blocked
+                .iter()
+                .any(|px| cookie == format!("_px={}", px));
+            self.cookies.lock().unwrap().push(cookie);
+            Ok(if blocked {
+                BLOCK_RESPONSE
+            } else {
+                SEARCH_RESPONSE
+            }
+            .to_string())
+        }
+    }
+
+    async fn send(request_sender: &RequestSender, args: &Arguments) -> Result<Value, ScraperError> {
+        let job = ScrapeJob::new("Mica Capacitors", &["Capacitance".to_string()], &[]).unwrap();
+        request_sender
+            .send_request(args, &job, RequestType::Attributes, &ResponseHandler::new())
+            .await
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let transport = FlakyTransport::new(
+            1,
+            || ScraperError::Network("timed out".to_string()),
+            SEARCH_RESPONSE,
+        );
+        let request_sender = RequestSender::with_transport(transport.clone());
+        assert!(send(&request_sender, &Arguments::default()).await.is_ok());
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_failures() {
+        let transport = FlakyTransport::new(1, || ScraperError::HttpStatus(404), SEARCH_RESPONSE);
+        let request_sender = RequestSender::with_transport(transport.clone());
+        let error = send(&request_sender, &Arguments::default())
             .await
-            .expect("Failed to send request");
-        let response_string = response.text().await.map_err(|e| {
-            Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to parse response: {}", e),
-            )
-        })?;
-        let response = serde_json::from_str(&response_string).map_err(|e| {
-            debug!("Raw response string: {}", response_string);
-            Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to deserialize JSON: {}", e),
-            )
-        })?;
-        Ok(response)
+            .unwrap_err();
+        assert!(matches!(error, ScraperError::HttpStatus(404)));
+        assert_eq!(transport.requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rotates_to_the_next_session_when_one_is_blocked() {
+        let transport = Arc::new(SessionTransport {
+            blocked: vec!["a", "c", "fallback"],
+            cookies: Mutex::default(),
+        });
+        let sessions = ["a", "b", "c"].map(|px| Session {
+            px: px.to_string(),
+            user_agent: None,
+        });
+        let request_sender = RequestSender::with_transport(transport.clone())
+            .with_session_pool(SessionPool::new(sessions.to_vec()));
+        let args = Arguments {
+            px: Some("fallback".to_string()),
+            ..Default::default()
+        };
+
+        assert!(send(&request_sender, &args).await.is_ok());
+        assert!(send(&request_sender, &args).await.is_ok());
+        assert_eq!(
+            request_sender.session_health_message().unwrap(),
+            "sessions: 1/3 active"
+        );
+        assert_eq!(
+            *transport.cookies.lock().unwrap(),
+            ["_px=a", "_px=b", "_px=c", "_px=b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_a_block_once_every_session_is_retired() {
+        let transport = Arc::new(SessionTransport {
+            blocked: vec!["a", "fallback"],
+            cookies: Mutex::default(),
+        });
+        let request_sender = RequestSender::with_transport(transport.clone()).with_session_pool(
+            SessionPool::new(vec![Session {
+                px: "a".to_string(),
+                user_agent: None,
+            }]),
+        );
+        let args = Arguments {
+            px: Some("fallback".to_string()),
+            ..Default::default()
+        };
+
+        let error = send(&request_sender, &args).await.unwrap_err();
+        assert!(matches!(error, ScraperError::Blocked));
+        assert_eq!(
+            *transport.cookies.lock().unwrap(),
+            ["_px=a", "_px=fallback"]
+        );
     }
 }
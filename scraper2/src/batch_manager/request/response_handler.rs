@@ -1,17 +1,81 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use anyhow::Result;
-use serde_json::{Error, Value};
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use tokio::sync::{oneshot, Mutex};
 
+use crate::batch_manager::request::models::{
+    shape_differences, AttributeSearch, PartSearch, SearchData, SearchResponse, SearchResult,
+};
 use crate::batch_manager::types::{
     AttributeBucket, AttributeBucketCombination, AttributeBucketCombinations, AttributeBuckets,
 };
+use crate::error::ScraperError;
+
+/// Markers that only appear in the captcha page served by PerimeterX.
+const PERIMETERX_CHALLENGE_MARKERS: &[&str] = &["px-captcha", "_pxAppId", "captcha.js"];
+
+/// What a raw response turned out to be.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ResponseClass {
+    /// The PerimeterX captcha HTML page.
+    PerimeterXChallenge,
+    /// The JSON block payload PerimeterX serves to API clients.
+    PerimeterXBlock {
+        app_id: Option<String>,
+        uuid: Option<String>,
+    },
+    /// A GraphQL response carrying `errors`.
+    GraphQlErrors(Vec<String>),
+    /// A search that matched no parts.
+    EmptySearch,
+    /// A search with results.
+    Data,
+    /// Anything else, e.g. HTML that is not a captcha or JSON without `data.search`.
+    Malformed(String),
+}
+
+/// The number of responses of each `ResponseClass` a `ResponseHandler` has seen.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ResponseCounts {
+    pub(crate) data: usize,
+    pub(crate) empty_search: usize,
+    pub(crate) graphql_errors: usize,
+    pub(crate) perimeterx_challenges: usize,
+    pub(crate) perimeterx_blocks: usize,
+    pub(crate) malformed: usize,
+}
+
+impl ResponseCounts {
+    /// The number of responses seen, of any class.
+    pub(crate) fn total(&self) -> usize {
+        self.data
+            + self.empty_search
+            + self.graphql_errors
+            + self.perimeterx_challenges
+            + self.perimeterx_blocks
+            + self.malformed
+    }
+}
+
+#[derive(Default)]
+struct ResponseCounters {
+    data: AtomicUsize,
+    empty_search: AtomicUsize,
+    graphql_errors: AtomicUsize,
+    perimeterx_challenges: AtomicUsize,
+    perimeterx_blocks: AtomicUsize,
+    malformed: AtomicUsize,
+}
 
-/// Handles the extraction of data from JSON responses.
+/// Handles the classification of raw responses and the extraction of data from JSON responses.
 pub(crate) struct ResponseHandler {
     metadata_channel_tx: Mutex<Option<oneshot::Sender<Value>>>,
     pub metadata_channel_rx: Mutex<Option<oneshot::Receiver<Value>>>,
+    counters: ResponseCounters,
+    strict: bool,
 }
 
 impl ResponseHandler {
@@ -21,6 +85,137 @@ impl ResponseHandler {
         ResponseHandler {
             metadata_channel_tx: Mutex::new(Some(metadata_channel_tx)),
             metadata_channel_rx: Mutex::new(Some(metadata_channel_rx)),
+            counters: ResponseCounters::default(),
+            strict: false,
+        }
+    }
+
+    /// Makes extraction reject responses whose shape differs from the models in any way, instead
+    /// of only when a field the scraper needs is missing or has the wrong type.
+    pub(crate) fn with_strict_validation(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Works out what kind of response Octopart (or PerimeterX in front of it) sent.
+    ///
+    /// # Arguments
+    /// * `raw_response` - The response body as received.
+    ///
+    /// # Returns
+    /// The `ResponseClass`, together with the parsed JSON if the body was JSON.
+    pub(crate) fn classify(raw_response: &str) -> (ResponseClass, Option<Value>) {
+        let json: Value = match serde_json::from_str(raw_response) {
+            Ok(json) => json,
+            Err(e) => {
+                let class = if PERIMETERX_CHALLENGE_MARKERS
+                    .iter()
+                    .any(|marker| raw_response.contains(marker))
+                {
+                    ResponseClass::PerimeterXChallenge
+                } else {
+                    ResponseClass::Malformed(format!("Failed to deserialize JSON: {}", e))
+                };
+                return (class, None);
+            }
+        };
+
+        let class = if json.get("appId").is_some()
+            && (json.get("uuid").is_some() || json.get("blockScript").is_some())
+        {
+            ResponseClass::PerimeterXBlock {
+                app_id: json["appId"].as_str().map(str::to_string),
+                uuid: json["uuid"].as_str().map(str::to_string),
+            }
+        } else if let Some(errors) = json["errors"]
+            .as_array()
+            .filter(|errors| !errors.is_empty())
+        {
+            ResponseClass::GraphQlErrors(
+                errors
+                    .iter()
+                    .map(|error| match error["message"].as_str() {
+                        Some(message) => message.to_string(),
+                        None => error.to_string(),
+                    })
+                    .collect(),
+            )
+        } else {
+            match json.pointer("/data/search") {
+                Some(search) if search.is_object() => {
+                    if search["hits"].as_u64() == Some(0) {
+                        ResponseClass::EmptySearch
+                    } else {
+                        ResponseClass::Data
+                    }
+                }
+                _ => ResponseClass::Malformed(
+                    "Invalid JSON structure: '/data/search' path not found".to_string(),
+                ),
+            }
+        };
+        (class, Some(json))
+    }
+
+    /// Classifies a raw response, counts and logs the classification, and returns the JSON of
+    /// responses that carry search data.
+    ///
+    /// # Arguments
+    /// * `raw_response` - The response body returned by `RequestSender::send_request`.
+    ///
+    /// # Returns
+    /// The parsed JSON for `Data` and `EmptySearch` responses, or the matching `ScraperError`:
+    /// `Blocked` for either kind of PerimeterX block, `GraphQl` or `MalformedResponse`.
+    pub(crate) fn classify_response(&self, raw_response: &str) -> Result<Value, ScraperError> {
+        let (class, json) = Self::classify(raw_response);
+        let counter = match &class {
+            ResponseClass::PerimeterXChallenge => &self.counters.perimeterx_challenges,
+            ResponseClass::PerimeterXBlock { .. } => &self.counters.perimeterx_blocks,
+            ResponseClass::GraphQlErrors(_) => &self.counters.graphql_errors,
+            ResponseClass::EmptySearch => &self.counters.empty_search,
+            ResponseClass::Data => &self.counters.data,
+            ResponseClass::Malformed(_) => &self.counters.malformed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        match class {
+            ResponseClass::Data | ResponseClass::EmptySearch => {
+                debug!("Response classified as {:?}", class);
+                Ok(json.unwrap_or_default())
+            }
+            ResponseClass::PerimeterXChallenge => {
+                warn!("PerimeterX served a captcha challenge");
+                Err(ScraperError::Blocked)
+            }
+            ResponseClass::PerimeterXBlock { app_id, uuid } => {
+                warn!(
+                    "PerimeterX blocked the request (app id: {}, uuid: {})",
+                    app_id.as_deref().unwrap_or("unknown"),
+                    uuid.as_deref().unwrap_or("unknown")
+                );
+                Err(ScraperError::Blocked)
+            }
+            ResponseClass::GraphQlErrors(messages) => {
+                warn!("GraphQL errors: {}", messages.join("; "));
+                Err(ScraperError::GraphQl(messages))
+            }
+            ResponseClass::Malformed(message) => {
+                debug!("Malformed response: {}", raw_response);
+                Err(ScraperError::MalformedResponse(message))
+            }
+        }
+    }
+
+    /// The number of responses of each class seen so far.
+    pub(crate) fn response_counts(&self) -> ResponseCounts {
+        let counters = &self.counters;
+        ResponseCounts {
+            data: counters.data.load(Ordering::Relaxed),
+            empty_search: counters.empty_search.load(Ordering::Relaxed),
+            graphql_errors: counters.graphql_errors.load(Ordering::Relaxed),
+            perimeterx_challenges: counters.perimeterx_challenges.load(Ordering::Relaxed),
+            perimeterx_blocks: counters.perimeterx_blocks.load(Ordering::Relaxed),
+            malformed: counters.malformed.load(Ordering::Relaxed),
         }
     }
 
@@ -28,6 +223,38 @@ impl ResponseHandler {
         self.metadata_channel_rx.lock().await.take()
     }
 
+    /// Deserializes a response that `classify_response` accepted into its models.
+    ///
+    /// In strict mode the response must also match the models exactly: any field that is missing
+    /// or that the models do not know about is reported as `ScraperError::UnexpectedShape`.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON returned by `classify_response`.
+    ///
+    /// # Returns
+    /// The typed `search`, or `MalformedResponse` if a field is missing or has the wrong type.
+    fn parse_search<S>(&self, json: Value) -> Result<S, ScraperError>
+    where
+        S: DeserializeOwned + Serialize,
+    {
+        let original = self.strict.then(|| json.clone());
+        let response: SearchResponse<S> = serde_json::from_value(json).map_err(|e| {
+            ScraperError::MalformedResponse(format!("Unexpected search response: {}", e))
+        })?;
+
+        if let Some(original) = original {
+            let typed = serde_json::to_value(&response).map_err(|e| {
+                ScraperError::MalformedResponse(format!("Failed to serialize search: {}", e))
+            })?;
+            let mut differences = Vec::new();
+            shape_differences(&original, &typed, "", &mut differences);
+            if !differences.is_empty() {
+                return Err(ScraperError::UnexpectedShape(differences));
+            }
+        }
+        Ok(response.data.search)
+    }
+
     /// Extracts attribute buckets from the provided JSON value.
     ///
     /// # Arguments
@@ -35,54 +262,37 @@ impl ResponseHandler {
     /// * `attribute_bucket_display_values` - A slice of attribute display value strings to be extracted.
     ///
     /// # Returns
-    /// A `Result` containing `AttributeBuckets` on success or an `Error` if extraction fails.
+    /// A `Result` containing `AttributeBuckets` on success or a `ScraperError` if extraction fails.
     pub(crate) async fn extract_attribute_buckets(
         &self,
         json: Value,
         attribute_bucket_display_values: &[String],
-    ) -> Result<AttributeBuckets, Error> {
-        let mut attribute_bucket_map = HashMap::new();
-        if let Some(spec_aggs) = json
-            .pointer("/data/search/spec_aggs")
-            .and_then(|v| v.as_array())
-        {
-            for (attribute_bucket_display_value, attribute_data) in
-                attribute_bucket_display_values.iter().zip(spec_aggs.iter())
-            {
-                if let Some(buckets) = attribute_data.get("buckets").and_then(|v| v.as_array()) {
-                    let extracted_attribute_buckets = buckets
-                        .iter()
-                        .map(|bucket| {
-                            let count =
-                                bucket.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                            let display_value = bucket
-                                .get("display_value")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_string();
-                            let float_value = bucket
-                                .get("float_value")
-                                .and_then(|v| v.as_f64())
-                                .map(|v| v.to_string());
-                            AttributeBucket {
-                                component_count: count,
-                                display_value,
-                                float_value,
-                            }
-                        })
-                        .collect();
-
-                    attribute_bucket_map.insert(
-                        attribute_bucket_display_value.to_string(),
-                        extracted_attribute_buckets,
-                    );
-                }
-            }
+    ) -> Result<AttributeBuckets, ScraperError> {
+        let search: AttributeSearch = self.parse_search(json)?;
+        if self.strict && search.spec_aggs.len() != attribute_bucket_display_values.len() {
+            return Err(ScraperError::UnexpectedShape(vec![format!(
+                "{} spec_aggs for {} attributes",
+                search.spec_aggs.len(),
+                attribute_bucket_display_values.len()
+            )]));
         }
 
-        Ok(AttributeBuckets {
-            buckets: attribute_bucket_map,
-        })
+        let buckets = attribute_bucket_display_values
+            .iter()
+            .zip(search.spec_aggs)
+            .map(|(attribute_bucket_display_value, spec_agg)| {
+                (
+                    attribute_bucket_display_value.to_string(),
+                    spec_agg
+                        .buckets
+                        .into_iter()
+                        .map(AttributeBucket::from)
+                        .collect(),
+                )
+            })
+            .collect();
+
+        Ok(AttributeBuckets { buckets })
     }
 
     /// Extracts filter combinations from the JSON response based on given attribute IDs.
@@ -93,52 +303,42 @@ impl ResponseHandler {
     /// * `last_attribute_bucket_key` - The key of the last attribute bucket in the combination.
     ///
     /// # Returns
-    /// A `Result` containing `AttributeBucketCombination` on success or an `Error` if extraction fails.
+    /// A `Result` containing `AttributeBucketCombination` on success or a `ScraperError` if extraction fails.
     pub(crate) async fn extract_attribute_bucket_combinations(
         &self,
         json: Value,
         current_attribute_bucket_combinations: HashMap<String, AttributeBucket>,
         last_attribute_bucket_key: String,
-    ) -> Result<AttributeBucketCombinations, Error> {
-        let mut attribute_bucket_combinations = AttributeBucketCombinations::default();
-        if let Some(spec_aggs) = json
-            .pointer("/data/search/spec_aggs/0/buckets")
-            .and_then(|v| v.as_array())
-        {
-            for attribute_bucket in spec_aggs.iter() {
-                let mut current_attribute_bucket_combinations =
-                    current_attribute_bucket_combinations.clone();
-                current_attribute_bucket_combinations.insert(
-                    last_attribute_bucket_key.clone(),
-                    AttributeBucket {
-                        component_count: attribute_bucket
-                            .get("count")
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0) as usize,
-                        display_value: attribute_bucket
-                            .get("display_value")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .to_string(),
-                        float_value: attribute_bucket
-                            .get("float_value")
-                            .and_then(|v| v.as_f64())
-                            .map(|v| v.to_string()),
-                    },
-                );
-                let filter_combination = AttributeBucketCombination {
-                    attribute_bucket_combination: current_attribute_bucket_combinations,
-                    component_count: attribute_bucket
-                        .get("count")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as usize,
-                };
-                attribute_bucket_combinations
-                    .combinations
-                    .push(filter_combination);
-            }
+    ) -> Result<AttributeBucketCombinations, ScraperError> {
+        let search: AttributeSearch = self.parse_search(json)?;
+        if self.strict && search.spec_aggs.len() != 1 {
+            return Err(ScraperError::UnexpectedShape(vec![format!(
+                "{} spec_aggs for 1 attribute",
+                search.spec_aggs.len()
+            )]));
         }
-        Ok(attribute_bucket_combinations)
+
+        let combinations = search
+            .spec_aggs
+            .into_iter()
+            .next()
+            .map(|spec_agg| spec_agg.buckets)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bucket| {
+                let mut attribute_bucket_combination =
+                    current_attribute_bucket_combinations.clone();
+                let attribute_bucket = AttributeBucket::from(bucket);
+                let component_count = attribute_bucket.component_count;
+                attribute_bucket_combination
+                    .insert(last_attribute_bucket_key.clone(), attribute_bucket);
+                AttributeBucketCombination {
+                    attribute_bucket_combination,
+                    component_count,
+                }
+            })
+            .collect();
+        Ok(AttributeBucketCombinations { combinations })
     }
 
     /// Extracts components from JSON response and sends metadata once.
@@ -150,42 +350,247 @@ impl ResponseHandler {
     /// * `json` - JSON with response data.
     ///
     /// # Returns
-    /// `Vec<Value>` of components on success, or `anyhow::Error` on failure.
-    pub(crate) async fn extract_components(&self, json: Value) -> Result<Vec<Value>> {
-        let metadata = self.get_component_response_metadata(json.clone()).await?;
+    /// The `SearchResult` of every component on success, or a `ScraperError` on failure.
+    pub(crate) async fn extract_components(
+        &self,
+        json: Value,
+    ) -> Result<Vec<SearchResult>, ScraperError> {
+        let mut search: PartSearch = self.parse_search(json)?;
+        let results = std::mem::take(&mut search.results);
+
         if let Some(sender) = self.metadata_channel_tx.lock().await.take() {
+            let metadata = serde_json::to_value(SearchResponse {
+                data: SearchData { search },
+            })
+            .map_err(|e| {
+                ScraperError::MalformedResponse(format!("Failed to serialize metadata: {}", e))
+            })?;
             sender.send(metadata).map_err(|_| {
-                anyhow::Error::msg("Failed to send component response metadata over channel")
+                ScraperError::Task(
+                    "Failed to send component response metadata over channel".to_string(),
+                )
             })?;
         };
 
-        match json
-            .pointer("/data/search/results")
-            .and_then(|v| v.as_array())
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{ResponseClass, ResponseCounts, ResponseHandler};
+    use crate::batch_manager::job::ScrapeJob;
+    use crate::batch_manager::request::fake_transport::{FakeContext, FakeTransport};
+    use crate::batch_manager::request::request_sender::RequestType;
+    use crate::error::ScraperError;
+
+    fn mica_context() -> FakeContext {
+        let job = ScrapeJob::new(
+            "Mica Capacitors",
+            &["Capacitance".to_string(), "Case/Package".to_string()],
+            &[],
+        )
+        .unwrap();
+        FakeContext::new(FakeTransport::mica_capacitors(), job)
+    }
+
+    async fn send(context: &FakeContext, request_type: RequestType) -> Value {
+        context
+            .request_sender
+            .send_request(
+                &*context.args.read().await,
+                &context.job,
+                request_type,
+                &context.response_handler,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn extracts_attribute_buckets_per_attribute() {
+        let context = mica_context();
+        let response = send(&context, RequestType::Attributes).await;
+
+        let attribute_buckets = ResponseHandler::new()
+            .extract_attribute_buckets(response, &context.job.attribute_shortnames)
+            .await
+            .unwrap();
+
+        let capacitance = &attribute_buckets.buckets["capacitance"];
+        assert_eq!(capacitance.len(), 3);
+        assert!(capacitance
+            .iter()
+            .all(|bucket| bucket.float_value.is_some()));
+        let case_package = &attribute_buckets.buckets["case_package"];
+        assert_eq!(case_package.len(), 3);
+        assert!(case_package
+            .iter()
+            .all(|bucket| bucket.float_value.is_none()));
+        let total: usize = capacitance
+            .iter()
+            .map(|bucket| bucket.component_count)
+            .sum();
+        assert_eq!(total, 13);
+    }
+
+    #[tokio::test]
+    async fn extracts_components_and_sends_metadata_once() {
+        let context = mica_context();
+        let response_handler = ResponseHandler::new();
+        let receiver = response_handler.take_receiver().await.unwrap();
+
+        for start in [0, 10] {
+            let response = send(
+                &context,
+                RequestType::Components {
+                    filters: Default::default(),
+                    start,
+                    end: 10,
+                    sort: None,
+                },
+            )
+            .await;
+            let components = response_handler.extract_components(response).await.unwrap();
+            assert_eq!(components.len(), if start == 0 { 10 } else { 3 });
+        }
+
+        let metadata = receiver.await.unwrap();
+        assert_eq!(metadata.pointer("/data/search/results"), Some(&json!([])));
+        assert_eq!(
+            metadata.pointer("/data/search/applied_category/id"),
+            Some(&json!("6334"))
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_components_response_without_results() {
+        let error = ResponseHandler::new()
+            .extract_components(json!({ "data": { "search": null } }))
+            .await;
+        assert!(error.is_err());
+    }
+
+    #[tokio::test]
+    async fn strict_validation_reports_unexpected_shapes() {
+        let context = mica_context();
+        let strict_handler = ResponseHandler::new().with_strict_validation(true);
+        let response = send(
+            &context,
+            RequestType::Components {
+                filters: Default::default(),
+                start: 0,
+                end: 10,
+                sort: None,
+            },
+        )
+        .await;
+        let components = strict_handler.extract_components(response).await.unwrap();
+        assert_eq!(components[0].part.mpn, "MC001-0805");
+
+        let mut response = send(&context, RequestType::Attributes).await;
+        response["data"]["search"]["spec_aggs"][0]["buckets"][0]["median"] = json!(1);
+        let attribute_shortnames = &context.job.attribute_shortnames;
+        assert!(ResponseHandler::new()
+            .extract_attribute_buckets(response.clone(), attribute_shortnames)
+            .await
+            .is_ok());
+        match strict_handler
+            .extract_attribute_buckets(response, attribute_shortnames)
+            .await
         {
-            Some(results) => Ok(results.clone()),
-            None => Err(anyhow::Error::msg("No results found")),
+            Err(ScraperError::UnexpectedShape(differences)) => assert_eq!(
+                differences,
+                ["unexpected field /data/search/spec_aggs/0/buckets/0/median"]
+            ),
+            other => panic!("expected an unexpected shape, got {:?}", other),
         }
+
+        // A count that is not a number is an error in either mode instead of a zero count.
+        let mut response = send(&context, RequestType::Attributes).await;
+        response["data"]["search"]["spec_aggs"][0]["buckets"][0]["count"] = json!("many");
+        assert!(matches!(
+            ResponseHandler::new()
+                .extract_attribute_buckets(response, attribute_shortnames)
+                .await,
+            Err(ScraperError::MalformedResponse(_))
+        ));
     }
 
-    /// Extracts metadata from JSON response, removing component data.
-    ///
-    /// Modifies JSON to remove data at `/data/search/results`, extracting metadata.
-    /// Ensures the path exists before removal.
-    ///
-    /// # Arguments
-    /// * `json` - Mutable JSON with response data.
-    ///
-    /// # Returns
-    /// Modified JSON as `Value` on success, or `anyhow::Error` if path not found.
-    async fn get_component_response_metadata(&self, mut json: Value) -> Result<Value> {
-        if let Some(results) = json.pointer_mut("/data/search/results") {
-            *results = Value::Array(vec![]);
-        } else {
-            return Err(anyhow::Error::msg(
-                "Invalid JSON structure: '/data/search/results' path not found",
-            ));
+    #[test]
+    fn classifies_raw_responses() {
+        let cases = [
+            (
+                r#"<html><head><script src="/PXabc123/captcha/captcha.js"></script></head><body><div id="px-captcha"></div></body></html>"#,
+                ResponseClass::PerimeterXChallenge,
+            ),
+            (
+                r#"{"appId":"PXabc123","jsClientSrc":"/abc123/init.js","firstPartyEnabled":true,"vid":"","uuid":"0f0e-11","hostUrl":"/abc123/xhr","blockScript":"/abc123/captcha/captcha.js?a=c"}"#,
+                ResponseClass::PerimeterXBlock {
+                    app_id: Some("PXabc123".to_string()),
+                    uuid: Some("0f0e-11".to_string()),
+                },
+            ),
+            (
+                r#"{"data":null,"errors":[{"message":"Cannot query field \"foo\""}]}"#,
+                ResponseClass::GraphQlErrors(vec![r#"Cannot query field "foo""#.to_string()]),
+            ),
+            (
+                r#"{"data":{"search":{"hits":0,"results":[]}}}"#,
+                ResponseClass::EmptySearch,
+            ),
+            (
+                r#"{"data":{"search":{"hits":2,"results":[{},{}]}}}"#,
+                ResponseClass::Data,
+            ),
+        ];
+        for (raw_response, expected) in cases {
+            assert_eq!(ResponseHandler::classify(raw_response).0, expected);
         }
-        Ok(json)
+        assert!(matches!(
+            ResponseHandler::classify("<html>Service Unavailable</html>").0,
+            ResponseClass::Malformed(_)
+        ));
+        assert!(matches!(
+            ResponseHandler::classify(r#"{"data":{}}"#).0,
+            ResponseClass::Malformed(_)
+        ));
+    }
+
+    #[test]
+    fn counts_classified_responses() {
+        let response_handler = ResponseHandler::new();
+        assert!(response_handler
+            .classify_response(r#"{"data":{"search":{"hits":1}}}"#)
+            .is_ok());
+        assert!(response_handler
+            .classify_response(r#"{"data":{"search":{"hits":0}}}"#)
+            .is_ok());
+        assert!(matches!(
+            response_handler.classify_response(r#"<div id="px-captcha"></div>"#),
+            Err(ScraperError::Blocked)
+        ));
+        assert!(matches!(
+            response_handler.classify_response(r#"{"appId":"PXabc123","uuid":"1"}"#),
+            Err(ScraperError::Blocked)
+        ));
+        assert!(matches!(
+            response_handler.classify_response(r#"{"errors":[{"message":"bad"}]}"#),
+            Err(ScraperError::GraphQl(_))
+        ));
+
+        assert_eq!(
+            response_handler.response_counts(),
+            ResponseCounts {
+                data: 1,
+                empty_search: 1,
+                graphql_errors: 1,
+                perimeterx_challenges: 1,
+                perimeterx_blocks: 1,
+                malformed: 0,
+            }
+        );
     }
 }
@@ -0,0 +1,163 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use anyhow::{bail, Context, Result};
+use log::warn;
+use serde::Deserialize;
+
+/// A `_px` cookie and the user agent it was issued to.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Session {
+    pub(crate) px: String,
+    /// Falls back to `--user-agent` when not given.
+    pub(crate) user_agent: Option<String>,
+}
+
+/// The contents of a `--sessions` file.
+///
+/// Example:
+/// ```toml
+/// [[sessions]]
+/// px = "..."
+/// user_agent = "Mozilla/5.0 ..."
+///
+/// [[sessions]]
+/// px = "..."
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SessionsFile {
+    sessions: Vec<Session>,
+}
+
+/// A set of sessions that requests are spread across in turn.
+///
+/// A session that PerimeterX blocks is retired for the rest of the run. Once every session is
+/// retired, requests fall back to the `--px` key, which is the only one the user is asked to
+/// replace.
+#[derive(Default)]
+pub(crate) struct SessionPool {
+    sessions: Vec<(Session, AtomicBool)>,
+    next: AtomicUsize,
+}
+
+impl SessionPool {
+    pub(crate) fn new(sessions: Vec<Session>) -> Self {
+        Self {
+            sessions: sessions
+                .into_iter()
+                .map(|session| (session, AtomicBool::new(false)))
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reads the sessions listed in the file at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sessions file {}", path.display()))?;
+        let sessions_file: SessionsFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse sessions file {}", path.display()))?;
+        if sessions_file.sessions.is_empty() {
+            bail!("Sessions file {} lists no sessions", path.display());
+        }
+        Ok(Self::new(sessions_file.sessions))
+    }
+
+    /// Picks the next session that has not been retired.
+    ///
+    /// # Returns
+    /// The index and session, or `None` when every session has been retired.
+    pub(crate) fn next_session(&self) -> Option<(usize, &Session)> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.sessions.len())
+            .map(|offset| (start + offset) % self.sessions.len())
+            .find(|&index| !self.sessions[index].1.load(Ordering::Relaxed))
+            .map(|index| (index, &self.sessions[index].0))
+    }
+
+    /// Stops handing out the session at `index` after PerimeterX blocked it.
+    pub(crate) fn retire(&self, index: usize) {
+        if !self.sessions[index].1.swap(true, Ordering::Relaxed) {
+            let (active, total) = self.health();
+            warn!(
+                "Session {} was blocked and retired; {}/{} sessions left",
+                index + 1,
+                active,
+                total
+            );
+        }
+    }
+
+    /// The number of active sessions and the size of the pool.
+    pub(crate) fn health(&self) -> (usize, usize) {
+        let active = self
+            .sessions
+            .iter()
+            .filter(|(_, retired)| !retired.load(Ordering::Relaxed))
+            .count();
+        (active, self.sessions.len())
+    }
+
+    /// A short description of the pool's health for the progress output, if there is a pool.
+    pub(crate) fn health_message(&self) -> Option<String> {
+        match self.health() {
+            (_, 0) => None,
+            (active, total) => Some(format!("sessions: {}/{} active", active, total)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{Session, SessionPool};
+
+    fn session(px: &str) -> Session {
+        Session {
+            px: px.to_string(),
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn rotates_through_active_sessions() {
+        let pool = SessionPool::new(vec![session("a"), session("b"), session("c")]);
+        let picked: Vec<_> = (0..4)
+            .map(|_| pool.next_session().unwrap().1.px.clone())
+            .collect();
+        assert_eq!(picked, ["a", "b", "c", "a"]);
+
+        pool.retire(1);
+        let picked: Vec<_> = (0..4)
+            .map(|_| pool.next_session().unwrap().1.px.clone())
+            .collect();
+        assert!(picked.iter().all(|px| px != "b"));
+        assert_eq!(pool.health_message().unwrap(), "sessions: 2/3 active");
+
+        pool.retire(0);
+        pool.retire(2);
+        assert!(pool.next_session().is_none());
+        assert_eq!(pool.health(), (0, 3));
+    }
+
+    #[test]
+    fn loads_sessions_from_a_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "[[sessions]]\npx = \"a\"\nuser_agent = \"Agent A\"\n\n[[sessions]]\npx = \"b\""
+        )
+        .unwrap();
+
+        let pool = SessionPool::load(file.path()).unwrap();
+        assert_eq!(pool.health(), (2, 2));
+        assert_eq!(
+            pool.next_session().unwrap().1.user_agent.as_deref(),
+            Some("Agent A")
+        );
+        assert!(SessionPool::default().health_message().is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::config::constants::ENDPOINT;
+use crate::error::ScraperError;
+
+/// Delivers a GraphQL payload to Octopart and returns the raw response body.
+///
+/// `RequestSender` builds headers and payloads and parses the response; everything that touches
+/// the network goes through this trait so it can be swapped out.
+#[async_trait]
+pub(crate) trait Transport: Send + Sync {
+    /// Posts `body` with the given headers.
+    ///
+    /// # Returns
+    /// The response body as a string, or a `ScraperError` if the request could not be completed.
+    async fn post(&self, headers: HeaderMap, body: &Value) -> Result<String, ScraperError>;
+}
+
+/// Sends requests to the Octopart `ENDPOINT` over HTTP.
+///
+/// Unsuccessful statuses are reported as `ScraperError::HttpStatus`, except for `403`, which is
+/// how PerimeterX serves its blocks; those bodies are returned for `RequestSender` to classify.
+pub(crate) struct HttpTransport {
+    client: Client,
+}
+
+impl HttpTransport {
+    pub(crate) fn new() -> Result<Self, ScraperError> {
+        let client = Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .connection_verbose(true)
+            .http1_only()
+            .http1_title_case_headers()
+            .cookie_store(true)
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| ScraperError::Network(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn post(&self, headers: HeaderMap, body: &Value) -> Result<String, ScraperError> {
+        let response = self
+            .client
+            .post(ENDPOINT)
+            .headers(headers)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| ScraperError::Network(format!("Failed to send request: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::FORBIDDEN {
+            return Err(ScraperError::HttpStatus(status.as_u16()));
+        }
+
+        response
+            .text()
+            .await
+            .map_err(|e| ScraperError::Network(format!("Failed to read response: {}", e)))
+    }
+}
+
+/// A recorded request body and the raw response Octopart returned for it.
+#[derive(Serialize, Deserialize)]
+struct Cassette {
+    request: Value,
+    response: String,
+}
+
+/// Passes requests through to another transport and saves every request/response pair to a
+/// directory, one file per distinct payload.
+pub(crate) struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    dir: PathBuf,
+}
+
+impl RecordingTransport {
+    pub(crate) fn new(inner: Arc<dyn Transport>, dir: &Path) -> Result<Self, ScraperError> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            inner,
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn post(&self, headers: HeaderMap, body: &Value) -> Result<String, ScraperError> {
+        let response = self.inner.post(headers, body).await?;
+        let cassette = Cassette {
+            request: body.clone(),
+            response,
+        };
+
+        // Write to a temporary file first so a concurrent replay never reads half a cassette.
+        let path = cassette_path(&self.dir, body);
+        let temporary_path = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(&cassette).map_err(Error::from)?;
+        fs::write(&temporary_path, content).await?;
+        fs::rename(&temporary_path, &path).await?;
+        Ok(cassette.response)
+    }
+}
+
+/// Answers requests from a directory written by `RecordingTransport`, without any network access.
+pub(crate) struct ReplayTransport {
+    dir: PathBuf,
+}
+
+impl ReplayTransport {
+    pub(crate) fn new(dir: &Path) -> Result<Self, ScraperError> {
+        if !dir.is_dir() {
+            return Err(ScraperError::Io(Error::new(
+                ErrorKind::NotFound,
+                format!("Replay directory {} does not exist", dir.display()),
+            )));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn post(&self, _headers: HeaderMap, body: &Value) -> Result<String, ScraperError> {
+        let path = cassette_path(&self.dir, body);
+        let content = fs::read(&path).await.map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("No recorded response at {}: {}", path.display(), e),
+            )
+        })?;
+        let cassette: Cassette = serde_json::from_slice(&content).map_err(Error::from)?;
+        Ok(cassette.response)
+    }
+}
+
+/// The cassette file for a payload, named after the SHA-256 of its JSON.
+///
+/// Headers are left out of the key so that recordings stay valid when the PX key changes.
+/// `serde_json` keeps object keys sorted, so equal payloads always serialize the same way.
+fn cassette_path(dir: &Path, body: &Value) -> PathBuf {
+    let digest = Sha256::digest(body.to_string().as_bytes());
+    dir.join(format!("{:x}.json", digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::header::HeaderMap;
+    use serde_json::json;
+
+    use super::{RecordingTransport, ReplayTransport, Transport};
+    use crate::batch_manager::request::fake_transport::FakeTransport;
+
+    #[tokio::test]
+    async fn replays_recorded_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let body = json!({
+            "operationName": "FilterModalSearch",
+            "variables": { "attribute_names": ["capacitance"], "filters": {} },
+        });
+
+        let recorder =
+            RecordingTransport::new(Arc::new(FakeTransport::mica_capacitors()), dir.path())
+                .unwrap();
+        let recorded = recorder.post(HeaderMap::new(), &body).await.unwrap();
+
+        let replayer = ReplayTransport::new(dir.path()).unwrap();
+        let replayed = replayer.post(HeaderMap::new(), &body).await.unwrap();
+        assert_eq!(recorded, replayed);
+
+        let unrecorded = json!({ "operationName": "PricesViewSearch", "variables": {} });
+        assert!(replayer.post(HeaderMap::new(), &unrecorded).await.is_err());
+    }
+}
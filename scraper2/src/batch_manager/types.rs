@@ -1,10 +1,56 @@
 use std::collections::HashMap;
 
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::batch_manager::request::models::Bucket;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) struct ComponentCount {
-    pub(crate) attribute_bucket_combination: Vec<AttributeBucket>,
+    pub(crate) attribute_bucket_combination: HashMap<String, AttributeBucket>,
     pub(crate) start: usize,
     pub(crate) end: usize,
+    pub(crate) sort: Option<ComponentSort>,
+}
+
+impl ComponentCount {
+    /// Returns a key identifying the page: its filters, start index and sort order.
+    pub(crate) fn page_key(&self) -> String {
+        let mut filters: Vec<_> = self
+            .attribute_bucket_combination
+            .iter()
+            .map(|(attribute, bucket)| format!("{}={}", attribute, bucket.filter_value()))
+            .collect();
+        filters.sort();
+        let sort = self
+            .sort
+            .as_ref()
+            .map(|sort| format!("{}:{}", sort.key, sort.direction.as_str()))
+            .unwrap_or_default();
+        format!("{}|{}|{}", filters.join("&"), self.start, sort)
+    }
+}
+
+/// The order in which a page of search results is requested.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ComponentSort {
+    pub(crate) key: String,
+    pub(crate) direction: SortDirection,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    /// The `SortDirection` GraphQL enum value.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "asc",
+            SortDirection::Descending => "desc",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -12,34 +58,63 @@ pub(crate) struct ComponentCounts {
     pub(crate) component_counts: Vec<ComponentCount>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub(crate) struct AttributeBucketCombination {
     pub(crate) attribute_bucket_combination: HashMap<String, AttributeBucket>,
     pub(crate) component_count: usize,
 }
 
-#[derive(Debug, Default)]
-pub(crate) struct AttributeBucketCombinations {
-    pub(crate) combinations: Vec<AttributeBucketCombination>,
+impl AttributeBucketCombination {
+    /// Returns a key identifying the combination's filters, leaving out `excluded_attribute`.
+    ///
+    /// A combination split by another attribute has the same key as its parent when that
+    /// attribute is excluded.
+    pub(crate) fn key_without(&self, excluded_attribute: &str) -> Vec<(String, String)> {
+        let mut key: Vec<_> = self
+            .attribute_bucket_combination
+            .iter()
+            .filter(|(attribute, _)| attribute.as_str() != excluded_attribute)
+            .map(|(attribute, bucket)| (attribute.clone(), bucket.filter_value()))
+            .collect();
+        key.sort();
+        key
+    }
 }
 
-// Pair of attribute buckets ->
-// e.g. if the attributes are voltage rating dc and capacitance, a bucket pair would be
-// (first: 10v, second: 1uf) for example where the id is the display name and the bucket is the
-// bucket value
-#[derive(Clone, Debug)]
-pub(crate) struct AttributeBucketPair {
-    pub(crate) third_last_attribute_bucket: Option<(String, AttributeBucket)>,
-    pub(crate) second_last_attribute_bucket: (String, AttributeBucket),
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct AttributeBucketCombinations {
+    pub(crate) combinations: Vec<AttributeBucketCombination>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct AttributeBucket {
     pub(crate) component_count: usize,
     pub(crate) display_value: String,
     pub(crate) float_value: Option<String>,
 }
 
+impl AttributeBucket {
+    /// The value used to filter a search by this bucket: the float value when the attribute is
+    /// numeric, the display value otherwise.
+    pub(crate) fn filter_value(&self) -> String {
+        self.float_value
+            .clone()
+            .unwrap_or_else(|| self.display_value.clone())
+    }
+}
+
+impl From<Bucket> for AttributeBucket {
+    fn from(bucket: Bucket) -> Self {
+        Self {
+            component_count: bucket.count,
+            display_value: bucket.display_value,
+            float_value: bucket
+                .float_value
+                .map(|float_value| float_value.to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct AttributeBuckets {
     pub(crate) buckets: HashMap<String, Vec<AttributeBucket>>,
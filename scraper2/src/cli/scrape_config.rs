@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{OutputFormat, OverwritePolicy};
+
+/// Run settings loaded from a `--config` TOML file.
+///
+/// Every field is optional; values given on the command line or through the environment take
+/// precedence over the ones in the file.
+///
+/// Example:
+/// ```toml
+/// px = "..."
+/// user_agent = "Mozilla/5.0 ..."
+/// category = "Ceramic Capacitors"
+/// attributes = ["Capacitance", "Voltage Rating (DC)"]
+/// split_fallback = ["case_package", "tolerance"]
+/// sort_sweep = "mpn"
+/// no_prompt = true
+/// sessions = "./sessions.toml"
+/// requests_per_second = 5.0
+/// burst = 10
+/// record = "./cassettes"
+/// strict = true
+/// format = "sqlite"
+/// database = "./data/components.sqlite"
+/// output_dir = "./snapshots"
+/// filename_template = "{category}_{year}"
+/// overwrite = "version"
+///
+/// # Optional: scrape several categories in one run instead of `category`/`attributes`.
+/// interleave = false
+///
+/// [[jobs]]
+/// category = "Mica Capacitors"
+/// attributes = ["Capacitance"]
+///
+/// [[jobs]]
+/// category = "Film Capacitors"
+/// attributes = ["Capacitance", "Voltage Rating (DC)"]
+/// split_fallback = ["dielectric", "case_package"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ScrapeConfig {
+    pub(crate) px: Option<String>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) category: Option<String>,
+    pub(crate) attributes: Option<Vec<String>>,
+    pub(crate) split_fallback: Option<Vec<String>>,
+    pub(crate) sort_sweep: Option<String>,
+    pub(crate) no_prompt: Option<bool>,
+    pub(crate) resume: Option<bool>,
+    pub(crate) interleave: Option<bool>,
+    pub(crate) sessions: Option<PathBuf>,
+    pub(crate) requests_per_second: Option<f64>,
+    pub(crate) burst: Option<u32>,
+    pub(crate) record: Option<PathBuf>,
+    pub(crate) replay: Option<PathBuf>,
+    pub(crate) strict: Option<bool>,
+    pub(crate) format: Option<OutputFormat>,
+    pub(crate) database: Option<PathBuf>,
+    pub(crate) output_dir: Option<PathBuf>,
+    pub(crate) filename_template: Option<String>,
+    pub(crate) overwrite: Option<OverwritePolicy>,
+    pub(crate) jobs: Option<Vec<ScrapeJobConfig>>,
+}
+
+/// One `[[jobs]]` entry of the config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ScrapeJobConfig {
+    pub(crate) category: String,
+    pub(crate) attributes: Vec<String>,
+    pub(crate) split_fallback: Option<Vec<String>>,
+}
+
+impl ScrapeConfig {
+    /// Reads and parses the config file at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+}
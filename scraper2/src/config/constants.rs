@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub const BATCH_SIZE: usize = 100;
 
 pub(crate) const ENDPOINT: &str = "https://octopart.com/api/v4/internal";
@@ -9,7 +11,46 @@ pub(crate) const OCTOPART_COMPONENT_REQUEST_LIMIT: usize = 100;
 /// The maximum number of components that can be scraped in a given category.
 pub(crate) const OCTOPART_COMPONENT_COMBINATION_LIMIT: usize = 1000;
 
+/// The default sustained request rate, shared by every request of a run.
+pub(crate) const DEFAULT_REQUESTS_PER_SECOND: f64 = 10.0;
+/// The default number of requests that may be sent back to back.
+pub(crate) const DEFAULT_REQUEST_BURST: u32 = 20;
+
+/// How many times a request that failed transiently (timeout, dropped connection, `5xx`) is retried.
+pub(crate) const MAX_REQUEST_RETRIES: u32 = 4;
+pub(crate) const REQUEST_BACKOFF_BASE_DELAY: Duration = Duration::from_millis(500);
+pub(crate) const REQUEST_BACKOFF_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How many times a task may fail, other than by being blocked, before its error is returned
+/// instead of retried.
+pub(crate) const MAX_TASK_FAILURES: usize = 5;
+
+/// The maximum number of attributes a category can be split by.
+pub(crate) const MAX_SPLIT_ATTRIBUTES: usize = 5;
+
+/// Attribute shortnames tried, in order, to split bucket combinations that are still over
+/// `OCTOPART_COMPONENT_COMBINATION_LIMIT` after the requested split attributes.
+pub(crate) const DEFAULT_SPLIT_FALLBACK_ATTRIBUTES: &[&str] = &[
+    "capacitance",
+    "inductance",
+    "voltagerating_dc_",
+    "case_package",
+    "tolerance",
+    "dielectric",
+    "temperaturecoefficient",
+    "packaging",
+    "manufacturerlifecyclestatus",
+];
+
 pub(crate) const DEFAULT_FILENAME: &str = "data";
 pub(crate) const DEFAULT_SAVE_DIR: &str = "./data";
+pub(crate) const DEFAULT_FILENAME_TEMPLATE: &str = "{category}";
 
 pub(crate) const METADATA_FILE_SUFFIX: &str = "metadata";
+pub(crate) const CHECKPOINT_FILE_SUFFIX: &str = "checkpoint";
+pub(crate) const HEADER_FILE_SUFFIX: &str = "header";
+pub(crate) const UNCLASSIFIED_FILE_SUFFIX: &str = "unclassified";
+pub(crate) const COMBINED_CSV_FILENAME: &str = "combined.csv";
+pub(crate) const DEFAULT_DATABASE_FILENAME: &str = "components.sqlite";
+/// The default name of the table `process` writes, as the Python processor named it.
+pub(crate) const PROCESSED_CSV_FILENAME: &str = "final.csv";
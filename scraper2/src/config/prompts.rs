@@ -8,7 +8,7 @@ use once_cell::sync::Lazy;
 use crate::cli::ArgumentType;
 
 pub(crate) static LAZY_PROGRESS_STYLE: Lazy<ProgressStyle> = Lazy::new(|| {
-    ProgressStyle::with_template("{spinner:.bold.green} [{elapsed_precise:.bold.white}] [{wide_bar:.bold.cyan/blue}] {count:.bold.white}/{total_count:.bold.white} ({eta:.bold.magenta})")
+    ProgressStyle::with_template("{spinner:.bold.green} [{elapsed_precise:.bold.white}] [{wide_bar:.bold.cyan/blue}] {count:.bold.white}/{total_count:.bold.white} ({eta:.bold.magenta}) {msg}")
         .unwrap()
         .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
         .with_key("count", |state: &ProgressState, w: &mut dyn Write| write!(w, "{}", state.pos()).unwrap())
@@ -0,0 +1,250 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result};
+
+use crate::batch_manager::SearchResult;
+
+/// The columns every flattened CSV starts with, in order.
+///
+/// Names follow what `flatten_json` produces from the JSON output, so the `processor` notebooks
+/// can read either.
+pub(crate) const PART_COLUMNS: &[&str] = &[
+    "part_id",
+    "part_mpn",
+    "part_manufacturer_id",
+    "part_manufacturer_name",
+    "part_category_id",
+    "part_best_datasheet_url",
+    "part_median_price_1000_converted_price",
+    "part_median_price_1000_converted_currency",
+];
+
+/// The column holding the display value of the spec with the given attribute shortname.
+pub(crate) fn spec_column(shortname: &str) -> String {
+    format!("part_specs_{}_display_value", shortname)
+}
+
+/// The attribute shortname of a column made by `spec_column`, or `None` for other columns.
+pub(crate) fn spec_shortname(column: &str) -> Option<&str> {
+    column
+        .strip_prefix("part_specs_")?
+        .strip_suffix("_display_value")
+}
+
+/// Flattens components into a CSV with one row per part.
+///
+/// The `PART_COLUMNS` come first, followed by one `part_specs_<shortname>_display_value` column for
+/// every spec seen across the components, sorted by name. A part without a spec leaves its cell
+/// empty.
+///
+/// # Arguments
+/// * `components` - The components of a category.
+///
+/// # Returns
+/// The CSV, header included.
+pub(crate) fn components_to_csv(components: &[SearchResult]) -> Result<Vec<u8>> {
+    let spec_shortnames: BTreeSet<_> = components
+        .iter()
+        .flat_map(|component| &component.part.specs)
+        .map(|spec| spec.attribute.shortname.as_str())
+        .collect();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(
+        PART_COLUMNS.iter().map(|column| column.to_string()).chain(
+            spec_shortnames
+                .iter()
+                .map(|shortname| spec_column(shortname)),
+        ),
+    )?;
+
+    for component in components {
+        let part = &component.part;
+        let display_values: HashMap<_, _> = part
+            .specs
+            .iter()
+            .map(|spec| {
+                (
+                    spec.attribute.shortname.as_str(),
+                    spec.display_value.as_str(),
+                )
+            })
+            .collect();
+        let price = part.median_price_1000.as_ref();
+        let row = [
+            part.id.clone(),
+            part.mpn.clone(),
+            part.manufacturer.id.clone(),
+            part.manufacturer.name.clone(),
+            part.category
+                .as_ref()
+                .map(|category| category.id.clone())
+                .unwrap_or_default(),
+            part.best_datasheet
+                .as_ref()
+                .map(|datasheet| datasheet.url.clone())
+                .unwrap_or_default(),
+            price
+                .and_then(|price| price.converted_price)
+                .map(|converted_price| converted_price.to_string())
+                .unwrap_or_default(),
+            price
+                .and_then(|price| price.converted_currency.clone())
+                .unwrap_or_default(),
+        ];
+        writer.write_record(
+            row.into_iter()
+                .chain(spec_shortnames.iter().map(|shortname| {
+                    display_values
+                        .get(shortname)
+                        .map(|display_value| display_value.to_string())
+                        .unwrap_or_default()
+                })),
+        )?;
+    }
+
+    writer.into_inner().context("Failed to flush CSV")
+}
+
+/// Whether `table` was written by `components_to_csv`, i.e. its header starts with
+/// `PART_COLUMNS`.
+pub(crate) fn has_part_columns(table: &[u8]) -> bool {
+    csv::Reader::from_reader(table)
+        .headers()
+        .map(|header| {
+            header.len() >= PART_COLUMNS.len()
+                && header
+                    .iter()
+                    .zip(PART_COLUMNS)
+                    .all(|(column, part_column)| column == *part_column)
+        })
+        .unwrap_or(false)
+}
+
+/// Merges CSVs written by `components_to_csv` into one.
+///
+/// The header is the union of the inputs' headers: `PART_COLUMNS` first, then every other column
+/// sorted by name. Rows keep the order of the inputs and leave the columns their input lacked
+/// empty.
+///
+/// # Arguments
+/// * `tables` - The CSVs to merge, header included.
+///
+/// # Returns
+/// The merged CSV, header included.
+pub(crate) fn combine_csvs(tables: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let mut headers = Vec::new();
+    let mut other_columns = BTreeSet::new();
+    for table in tables {
+        let header = csv::Reader::from_reader(table.as_slice())
+            .headers()?
+            .clone();
+        other_columns.extend(
+            header
+                .iter()
+                .filter(|column| !PART_COLUMNS.contains(column))
+                .map(str::to_string),
+        );
+        headers.push(header);
+    }
+    let columns: Vec<String> = PART_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .chain(other_columns)
+        .collect();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns)?;
+    for (table, header) in tables.iter().zip(headers) {
+        let positions: HashMap<_, _> = header
+            .iter()
+            .enumerate()
+            .map(|(position, column)| (column, position))
+            .collect();
+        for record in csv::Reader::from_reader(table.as_slice()).records() {
+            let record = record?;
+            writer.write_record(columns.iter().map(|column| {
+                positions
+                    .get(column.as_str())
+                    .and_then(|&position| record.get(position))
+                    .unwrap_or_default()
+            }))?;
+        }
+    }
+
+    writer.into_inner().context("Failed to flush CSV")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine_csvs, components_to_csv, has_part_columns};
+    use crate::batch_manager::{FakeComponent, SearchResult};
+
+    fn component(id: &str, category_id: &str, specs: &[(&str, &str)]) -> SearchResult {
+        FakeComponent::new(id, &format!("MPN-{}", id))
+            .with_category(category_id)
+            .with_manufacturer("Kemet, Inc.")
+            .with_price(0.25)
+            .with_datasheet(&format!("https://example.com/{}.pdf", id))
+            .with_specs(specs)
+            .build()
+    }
+
+    #[test]
+    fn flattens_specs_into_sorted_columns() {
+        let components = [
+            component("1", "6334", &[("tolerance", "5%"), ("capacitance", "10pF")]),
+            component("2", "6334", &[("voltagerating_dc_", "100V")]),
+        ];
+
+        let csv = String::from_utf8(components_to_csv(&components).unwrap()).unwrap();
+
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "part_id,part_mpn,part_manufacturer_id,part_manufacturer_name,part_category_id,\
+                 part_best_datasheet_url,part_median_price_1000_converted_price,\
+                 part_median_price_1000_converted_currency,part_specs_capacitance_display_value,\
+                 part_specs_tolerance_display_value,part_specs_voltagerating_dc__display_value",
+                "1,MPN-1,196,\"Kemet, Inc.\",6334,https://example.com/1.pdf,0.25,USD,10pF,5%,",
+                "2,MPN-2,196,\"Kemet, Inc.\",6334,https://example.com/2.pdf,0.25,USD,,,100V",
+            ]
+        );
+    }
+
+    #[test]
+    fn combines_csvs_with_different_spec_columns() {
+        let mica = components_to_csv(&[component("1", "6334", &[("capacitance", "10pF")])]);
+        let film = components_to_csv(&[component("2", "6333", &[("dielectric", "Polyester")])]);
+
+        let combined = combine_csvs(&[mica.unwrap(), film.unwrap()]).unwrap();
+
+        let mut reader = csv::Reader::from_reader(combined.as_slice());
+        let header = reader.headers().unwrap().clone();
+        assert_eq!(
+            header.iter().skip(8).collect::<Vec<_>>(),
+            [
+                "part_specs_capacitance_display_value",
+                "part_specs_dielectric_display_value"
+            ]
+        );
+        let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][8], "10pF");
+        assert_eq!(&rows[0][9], "");
+        assert_eq!(&rows[1][4], "6333");
+        assert_eq!(&rows[1][9], "Polyester");
+    }
+
+    #[test]
+    fn tells_category_csvs_from_other_csvs() {
+        let mica = components_to_csv(&[component("1", "6334", &[("capacitance", "10pF")])]);
+
+        assert!(has_part_columns(&mica.unwrap()));
+        assert!(!has_part_columns(
+            b"mpn,manufacturer,capacitance\nR76,Kemet,1e-6\n"
+        ));
+        assert!(!has_part_columns(b""));
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tokio::fs;
+
+use super::csv_export::spec_shortname;
+use super::reader::read_components;
+use crate::batch_manager::SearchResult;
+
+/// The spec shortnames holding a part's lifecycle status, in order of preference.
+const LIFECYCLE_SHORTNAMES: &[&str] = &["manufacturerlifecyclestatus", "lifecyclestatus"];
+
+/// The number of lifecycle transitions listed in the summary.
+const SUMMARY_TRANSITIONS: usize = 10;
+
+/// A part as it is identified across scrapes.
+///
+/// Octopart ids are not guaranteed to survive a year, so parts are matched by MPN and
+/// manufacturer instead.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub(crate) struct PartName {
+    mpn: String,
+    manufacturer: String,
+}
+
+/// The values of a part that are compared between scrapes.
+#[derive(Debug, Default)]
+pub(crate) struct PartSnapshot {
+    median_price: Option<f64>,
+    specs: BTreeMap<String, String>,
+}
+
+impl PartSnapshot {
+    fn lifecycle_status(&self) -> Option<&String> {
+        LIFECYCLE_SHORTNAMES
+            .iter()
+            .find_map(|shortname| self.specs.get(*shortname))
+    }
+}
+
+/// A value of a part that was added, removed or changed.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ValueChange {
+    #[serde(flatten)]
+    part: PartName,
+    old: Option<String>,
+    new: Option<String>,
+}
+
+/// A change in the median price of a part at 1000 units.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct PriceChange {
+    #[serde(flatten)]
+    part: PartName,
+    old: f64,
+    new: f64,
+    delta: f64,
+    /// `delta` as a fraction of `old`, or `None` when the part used to be free.
+    relative_delta: Option<f64>,
+}
+
+/// What changed in a category between two scrapes.
+#[derive(Debug, Serialize)]
+pub(crate) struct SnapshotDiff {
+    old_parts: usize,
+    new_parts: usize,
+    added: Vec<PartName>,
+    removed: Vec<PartName>,
+    /// Changed spec values, keyed by attribute shortname. Lifecycle statuses are reported in
+    /// `lifecycle_changes` instead.
+    spec_changes: BTreeMap<String, Vec<ValueChange>>,
+    /// Price changes of parts that have a price in both scrapes.
+    price_changes: Vec<PriceChange>,
+    lifecycle_changes: Vec<ValueChange>,
+}
+
+impl SnapshotDiff {
+    /// Compares the parts of two scrapes of the same category.
+    pub(crate) fn between(
+        old: &BTreeMap<PartName, PartSnapshot>,
+        new: &BTreeMap<PartName, PartSnapshot>,
+    ) -> Self {
+        let mut diff = Self {
+            old_parts: old.len(),
+            new_parts: new.len(),
+            added: new
+                .keys()
+                .filter(|name| !old.contains_key(name))
+                .cloned()
+                .collect(),
+            removed: old
+                .keys()
+                .filter(|name| !new.contains_key(name))
+                .cloned()
+                .collect(),
+            spec_changes: BTreeMap::new(),
+            price_changes: Vec::new(),
+            lifecycle_changes: Vec::new(),
+        };
+
+        for (name, old_part) in old {
+            let Some(new_part) = new.get(name) else {
+                continue;
+            };
+
+            let mut shortnames: Vec<_> =
+                old_part.specs.keys().chain(new_part.specs.keys()).collect();
+            shortnames.sort();
+            shortnames.dedup();
+            for shortname in shortnames {
+                let (old_value, new_value) =
+                    (old_part.specs.get(shortname), new_part.specs.get(shortname));
+                if old_value != new_value && !LIFECYCLE_SHORTNAMES.contains(&shortname.as_str()) {
+                    diff.spec_changes
+                        .entry(shortname.clone())
+                        .or_default()
+                        .push(ValueChange {
+                            part: name.clone(),
+                            old: old_value.cloned(),
+                            new: new_value.cloned(),
+                        });
+                }
+            }
+
+            if let (Some(old_price), Some(new_price)) =
+                (old_part.median_price, new_part.median_price)
+            {
+                if old_price != new_price {
+                    diff.price_changes.push(PriceChange {
+                        part: name.clone(),
+                        old: old_price,
+                        new: new_price,
+                        delta: new_price - old_price,
+                        relative_delta: (old_price != 0.0)
+                            .then(|| (new_price - old_price) / old_price),
+                    });
+                }
+            }
+
+            let (old_status, new_status) =
+                (old_part.lifecycle_status(), new_part.lifecycle_status());
+            if old_status != new_status {
+                diff.lifecycle_changes.push(ValueChange {
+                    part: name.clone(),
+                    old: old_status.cloned(),
+                    new: new_status.cloned(),
+                });
+            }
+        }
+
+        diff
+    }
+
+    /// A short human-readable account of the diff, for checking a new scrape at a glance.
+    pub(crate) fn summary(&self, old_path: &Path, new_path: &Path) -> String {
+        let mut summary = String::new();
+        // Writing to a String cannot fail.
+        let _ = writeln!(
+            summary,
+            "{} ({} parts) -> {} ({} parts)",
+            old_path.display(),
+            self.old_parts,
+            new_path.display(),
+            self.new_parts
+        );
+        let _ = writeln!(summary, "Added: {} parts", self.added.len());
+        let _ = writeln!(summary, "Removed: {} parts", self.removed.len());
+
+        let _ = writeln!(
+            summary,
+            "Spec changes: {} values",
+            self.spec_changes.values().map(Vec::len).sum::<usize>()
+        );
+        for (shortname, changes) in &self.spec_changes {
+            let _ = writeln!(summary, "  {}: {} parts", shortname, changes.len());
+        }
+
+        let relative_deltas: Vec<f64> = self
+            .price_changes
+            .iter()
+            .filter_map(|change| change.relative_delta)
+            .collect();
+        let _ = write!(
+            summary,
+            "Median price changes: {} parts",
+            self.price_changes.len()
+        );
+        if !relative_deltas.is_empty() {
+            let mean = relative_deltas.iter().sum::<f64>() / relative_deltas.len() as f64;
+            let _ = write!(summary, ", {:+.1}% on average", mean * 100.0);
+        }
+        summary.push('\n');
+
+        let _ = writeln!(
+            summary,
+            "Lifecycle status changes: {} parts",
+            self.lifecycle_changes.len()
+        );
+        let mut transitions: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for change in &self.lifecycle_changes {
+            *transitions
+                .entry((
+                    change.old.as_deref().unwrap_or("none"),
+                    change.new.as_deref().unwrap_or("none"),
+                ))
+                .or_default() += 1;
+        }
+        let mut transitions: Vec<_> = transitions.into_iter().collect();
+        transitions.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        for ((old, new), count) in transitions.into_iter().take(SUMMARY_TRANSITIONS) {
+            let _ = writeln!(summary, "  {} -> {}: {} parts", old, new, count);
+        }
+
+        summary
+    }
+}
+
+/// Reads the parts of a category output written by `DataManager`.
+///
+/// JSON, NDJSON and CSV outputs are read according to their extension. When a part appears more
+/// than once, its first copy is used.
+///
+/// # Arguments
+/// * `path` - The `.json`, `.ndjson` or `.csv` file of a category.
+///
+/// # Returns
+/// The parts, keyed by MPN and manufacturer.
+pub(crate) async fn load_parts(path: &Path) -> Result<BTreeMap<PartName, PartSnapshot>> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    match extension.as_ref() {
+        "json" | "ndjson" => Ok(parts_from_components(read_components(path).await?)),
+        "csv" => {
+            let content = fs::read(path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            parts_from_csv(&content)
+        }
+        _ => bail!(
+            "Cannot compare {}; expected a .json, .ndjson or .csv output",
+            path.display()
+        ),
+    }
+}
+
+fn parts_from_components(components: Vec<SearchResult>) -> BTreeMap<PartName, PartSnapshot> {
+    let mut parts = BTreeMap::new();
+    for component in components {
+        let part = component.part;
+        let name = PartName {
+            mpn: part.mpn,
+            manufacturer: part.manufacturer.name,
+        };
+        parts.entry(name).or_insert_with(|| PartSnapshot {
+            median_price: part
+                .median_price_1000
+                .and_then(|price| price.converted_price),
+            specs: part
+                .specs
+                .into_iter()
+                .map(|spec| (spec.attribute.shortname, spec.display_value))
+                .collect(),
+        });
+    }
+    parts
+}
+
+fn parts_from_csv(content: &[u8]) -> Result<BTreeMap<PartName, PartSnapshot>> {
+    let mut reader = csv::Reader::from_reader(content);
+    let header = reader.headers()?.clone();
+    let position = |column: &str| {
+        header
+            .iter()
+            .position(|name| name == column)
+            .with_context(|| format!("The CSV has no {} column", column))
+    };
+    let mpn_position = position("part_mpn")?;
+    let manufacturer_position = position("part_manufacturer_name")?;
+    let price_position = position("part_median_price_1000_converted_price")?;
+    let spec_positions: Vec<_> = header
+        .iter()
+        .enumerate()
+        .filter_map(|(position, column)| Some((position, spec_shortname(column)?)))
+        .collect();
+
+    let mut parts = BTreeMap::new();
+    for record in reader.records() {
+        let record = record?;
+        let name = PartName {
+            mpn: record[mpn_position].to_string(),
+            manufacturer: record[manufacturer_position].to_string(),
+        };
+        parts.entry(name).or_insert_with(|| PartSnapshot {
+            median_price: record[price_position].parse().ok(),
+            specs: spec_positions
+                .iter()
+                .filter(|(position, _)| !record[*position].is_empty())
+                .map(|(position, shortname)| (shortname.to_string(), record[*position].to_string()))
+                .collect(),
+        });
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use super::{load_parts, SnapshotDiff};
+    use crate::batch_manager::FakeComponent;
+    use crate::data_manager::csv_export::components_to_csv;
+
+    fn component(mpn: &str, price: f64, specs: &[(&str, &str)]) -> serde_json::Value {
+        FakeComponent::new(mpn, mpn)
+            .with_price(price)
+            .with_specs(specs)
+            .into_value()
+    }
+
+    #[tokio::test]
+    async fn reports_changes_between_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_path = dir.path().join("mica_capacitors_2023.json");
+        let old = json!({ "results": [
+            component("A", 0.5, &[("capacitance", "10pF"), ("manufacturerlifecyclestatus", "Active")]),
+            component("B", 1.0, &[("capacitance", "22pF")]),
+        ]});
+        std::fs::write(&old_path, old.to_string()).unwrap();
+        let new_path = dir.path().join("mica_capacitors_2024.csv");
+        let new = [
+            component(
+                "A",
+                0.6,
+                &[
+                    ("capacitance", "12pF"),
+                    ("manufacturerlifecyclestatus", "Obsolete"),
+                ],
+            ),
+            component("C", 2.0, &[("capacitance", "47pF")]),
+        ]
+        .map(|component| serde_json::from_value(component).unwrap());
+        std::fs::write(&new_path, components_to_csv(&new).unwrap()).unwrap();
+
+        let diff = SnapshotDiff::between(
+            &load_parts(&old_path).await.unwrap(),
+            &load_parts(&new_path).await.unwrap(),
+        );
+
+        let report = serde_json::to_value(&diff).unwrap();
+        assert_eq!(
+            report["added"],
+            json!([{ "mpn": "C", "manufacturer": "Kemet" }])
+        );
+        assert_eq!(
+            report["removed"],
+            json!([{ "mpn": "B", "manufacturer": "Kemet" }])
+        );
+        assert_eq!(
+            report["spec_changes"],
+            json!({ "capacitance": [
+                { "mpn": "A", "manufacturer": "Kemet", "old": "10pF", "new": "12pF" }
+            ]})
+        );
+        assert_eq!(
+            report["lifecycle_changes"],
+            json!([{ "mpn": "A", "manufacturer": "Kemet", "old": "Active", "new": "Obsolete" }])
+        );
+        let relative_delta = report["price_changes"][0]["relative_delta"]
+            .as_f64()
+            .unwrap();
+        assert!((relative_delta - 0.2).abs() < 1e-9);
+
+        let summary = diff.summary(Path::new("2023.json"), Path::new("2024.csv"));
+        assert!(summary.contains("Added: 1 parts"));
+        assert!(summary.contains("+20.0% on average"));
+        assert!(summary.contains("Active -> Obsolete: 1 parts"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+
+use super::DataManager;
+use crate::config::constants::DEFAULT_FILENAME;
+
+/// The placeholders a `--filename-template` may use.
+const PLACEHOLDERS: &[&str] = &["category", "date", "year", "attributes"];
+
+/// The name, without extension, given to the files a category is saved to.
+///
+/// Placeholders are written in braces:
+/// * `{category}` - The category name, e.g. `ceramic_capacitors`.
+/// * `{date}` - The date of the scrape, e.g. `2024-03-01`.
+/// * `{year}` - The year of the scrape, e.g. `2024`.
+/// * `{attributes}` - The attribute shortnames the category was split by, joined with `_`.
+///
+/// Example: `{category}_{year}` names the yearly snapshot of ceramic capacitors
+/// `ceramic_capacitors_2024`.
+#[derive(Clone, Debug)]
+pub(crate) struct FilenameTemplate {
+    template: String,
+}
+
+impl FilenameTemplate {
+    /// Checks that `template` only uses known placeholders and stays inside the output directory.
+    pub(crate) fn new(template: &str) -> Result<Self> {
+        if template.trim().is_empty() {
+            bail!("The filename template is empty");
+        }
+        if template.contains(['/', '\\']) {
+            bail!(
+                "The filename template {:?} contains a path separator",
+                template
+            );
+        }
+
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            if &rest[start..start + 1] == "}" {
+                bail!("The filename template {:?} has an unmatched '}}'", template);
+            }
+            let Some(length) = rest[start..].find('}') else {
+                bail!("The filename template {:?} has an unmatched '{{'", template);
+            };
+            let placeholder = &rest[start + 1..start + length];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "Unknown placeholder {{{}}} in the filename template; expected one of {}",
+                    placeholder,
+                    PLACEHOLDERS
+                        .iter()
+                        .map(|placeholder| format!("{{{}}}", placeholder))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            rest = &rest[start + length + 1..];
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+        })
+    }
+
+    /// Checks that the jobs of a run are saved under distinct names: with several jobs the
+    /// template must use `{category}`, and when a category is listed more than once, e.g. split by
+    /// different attributes, `{attributes}` too.
+    ///
+    /// # Arguments
+    /// * `categories` - The category of every job in the run.
+    pub(crate) fn check_jobs(&self, categories: &[&str]) -> Result<()> {
+        if categories.len() > 1 && !self.template.contains("{category}") {
+            bail!(
+                "The filename template {:?} has no {{category}}, so the {} jobs would be saved \
+                 under the same name",
+                self.template,
+                categories.len()
+            );
+        }
+        let repeated = categories
+            .iter()
+            .enumerate()
+            .find(|(index, category)| categories[..*index].contains(category));
+        if let Some((_, category)) = repeated {
+            if !self.template.contains("{attributes}") {
+                bail!(
+                    "{} is listed more than once, but the filename template {:?} has no \
+                     {{attributes}}, so its jobs would be saved under the same name",
+                    category,
+                    self.template
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills in the placeholders for a category scraped at `timestamp`.
+    pub(crate) fn render(
+        &self,
+        category_name: &str,
+        attribute_shortnames: &[String],
+        timestamp: DateTime<Utc>,
+    ) -> String {
+        let category = if category_name.is_empty() {
+            DEFAULT_FILENAME
+        } else {
+            category_name
+        };
+        self.template
+            .replace("{category}", &DataManager::sanitize_filename(category))
+            .replace("{date}", &timestamp.format("%Y-%m-%d").to_string())
+            .replace("{year}", &timestamp.format("%Y").to_string())
+            .replace(
+                "{attributes}",
+                &DataManager::sanitize_filename(&attribute_shortnames.join("_")),
+            )
+    }
+}
+
+/// The path `path` is saved to instead when it exists and the overwrite policy is `version`: the
+/// same name with the time of saving appended, e.g. `mica_capacitors_20240301T120000Z.json`.
+pub(crate) fn versioned_path(path: &Path, timestamp: DateTime<Utc>) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let version = timestamp.format("%Y%m%dT%H%M%SZ");
+    let filename = match path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, version, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, version),
+    };
+    path.with_file_name(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{versioned_path, FilenameTemplate};
+
+    #[test]
+    fn renders_placeholders() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let attributes = ["capacitance".to_string(), "voltagerating_dc_".to_string()];
+
+        let yearly = FilenameTemplate::new("{category}_{year}").unwrap();
+        assert_eq!(
+            yearly.render("Ceramic Capacitors", &attributes, timestamp),
+            "ceramic_capacitors_2024"
+        );
+        let detailed = FilenameTemplate::new("{date}-{category}-{attributes}").unwrap();
+        assert_eq!(
+            detailed.render("", &attributes, timestamp),
+            "2024-03-01-data-capacitance_voltagerating_dc_"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for template in ["", "{category", "category}", "{month}", "../{category}"] {
+            assert!(FilenameTemplate::new(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn requires_distinct_names_for_the_jobs_of_a_run() {
+        let template = |template: &str| FilenameTemplate::new(template).unwrap();
+        let mica_and_film = ["Mica Capacitors", "Film Capacitors"];
+        let mica_twice = ["Mica Capacitors", "Film Capacitors", "Mica Capacitors"];
+
+        assert!(template("capacitors_{year}")
+            .check_jobs(&["Mica Capacitors"])
+            .is_ok());
+        assert!(template("capacitors_{year}")
+            .check_jobs(&mica_and_film)
+            .is_err());
+        assert!(template("{category}").check_jobs(&mica_and_film).is_ok());
+        assert!(template("{category}").check_jobs(&mica_twice).is_err());
+        assert!(template("{category}_{attributes}")
+            .check_jobs(&mica_twice)
+            .is_ok());
+    }
+
+    #[test]
+    fn appends_the_time_to_versioned_paths() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            versioned_path(Path::new("./data/mica_capacitors.json"), timestamp),
+            Path::new("./data/mica_capacitors_20240301T120000Z.json")
+        );
+    }
+}
@@ -3,7 +3,9 @@ use std::{
     sync::Arc,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use log::debug;
 use serde_json::{json, Value};
 use tokio::{
     fs::{self, File},
@@ -11,14 +13,48 @@ use tokio::{
     sync::RwLock,
 };
 
+mod csv_export;
+mod diff;
+mod filename;
+mod ndjson;
+mod postgres;
+mod reader;
+mod sqlite;
+
+use diff::SnapshotDiff;
+use filename::versioned_path;
+pub(crate) use filename::FilenameTemplate;
+pub(crate) use ndjson::NdjsonWriter;
+use postgres::{PostgresLoader, TableSchema};
+use sqlite::SqliteStore;
+
 use crate::{
-    cli::{Arguments, Cli},
+    batch_manager::SearchResult,
+    cli::{Arguments, Cli, OverwritePolicy, YearlySnapshot},
     config::{
-        constants::{DEFAULT_FILENAME, DEFAULT_SAVE_DIR, METADATA_FILE_SUFFIX},
+        constants::{
+            COMBINED_CSV_FILENAME, DEFAULT_DATABASE_FILENAME, HEADER_FILE_SUFFIX,
+            METADATA_FILE_SUFFIX, PROCESSED_CSV_FILENAME, UNCLASSIFIED_FILE_SUFFIX,
+        },
         prompts::{print_error_message, print_info_message},
     },
+    error::ScraperError,
+    processor::{
+        classify::{CeramicClasses, ClassificationReport},
+        merge::MergedTable,
+        metrics,
+    },
 };
 
+/// The files a category's output was written to.
+#[derive(Debug)]
+pub(crate) struct SavedOutput {
+    /// The components, e.g. `./data/mica_capacitors.json`, or the database.
+    pub(crate) filepath: PathBuf,
+    /// The scraper metadata written next to the components, if any.
+    pub(crate) metadata_filepath: Option<PathBuf>,
+}
+
 #[derive(Default)]
 pub struct DataManager {
     args: Arc<RwLock<Arguments>>,
@@ -31,70 +67,319 @@ impl DataManager {
 }
 
 impl DataManager {
+    /// Saves the components of a category as one JSON document, next to the scraper metadata file.
+    ///
+    /// # Arguments
+    /// * `filename` - The name, without extension, rendered from the filename template.
+    /// * `data` - The components of every page.
+    /// * `octopart_metadata` - The Octopart search metadata the components are inserted into.
+    /// * `scraper_metadata` - The scraper's own metadata for the category.
+    ///
+    /// # Returns
+    /// The files written, or `None` if the overwrite policy kept an existing file, in which case
+    /// the metadata is not written either.
     pub(crate) async fn save_to_disk(
         &self,
-        data: Result<Vec<Result<Vec<Value>>>>,
+        filename: &str,
+        data: Result<Vec<Result<Vec<SearchResult>, ScraperError>>>,
         octopart_metadata: &mut Option<Value>,
         scraper_metadata: Option<Value>,
-    ) -> Result<()> {
-        // 1. Flatten the structure by handling potential errors
-        let cleaned_data: Vec<Value> = data?.into_iter().filter_map(Result::ok).flatten().collect();
+    ) -> Result<Option<SavedOutput>> {
+        // 1. Flatten the structure. Pages that failed were counted as missed by the component
+        // scraper and stay in the checkpoint, so they are only left out here.
+        let cleaned_data: Vec<SearchResult> =
+            data?.into_iter().filter_map(Result::ok).flatten().collect();
 
         // 2. Serialize and write to a file
         let file_content = self.serialize(cleaned_data, octopart_metadata);
 
-        // 3. Prepare filename and path
-        let filename = self
-            .args
-            .read()
-            .await
-            .category_name
-            .as_ref()
-            .map(|name| Self::sanitize_filename(name))
-            .unwrap_or_else(|| Self::sanitize_filename(DEFAULT_FILENAME));
-
-        // 4. Write component data to disk
-        let component_filepath = format!("{}/{}.json", DEFAULT_SAVE_DIR, filename);
-        self.save_json_to_file(file_content, &component_filepath)
+        // 3. Write component data to disk
+        let component_filepath = self.output_dir().await.join(format!("{}.json", filename));
+        let Some(component_filepath) = self
+            .save_json_to_file(file_content, &component_filepath)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        // 4. Write metadata next to it, under the same (possibly versioned) name
+        let metadata_filepath = self
+            .save_scraper_metadata(&component_filepath, scraper_metadata)
             .await?;
 
-        // 5. Write metadata to disk
-        if let Some(scraper_meta) = scraper_metadata {
-            let scraper_filepath = format!(
-                "{}/{}_{}.json",
-                DEFAULT_SAVE_DIR, filename, METADATA_FILE_SUFFIX
+        print_info_message("Done!", true);
+        Ok(Some(SavedOutput {
+            filepath: component_filepath,
+            metadata_filepath,
+        }))
+    }
+
+    /// Saves the components of a category as a flattened CSV, with one row per part and one
+    /// column per spec, next to the usual scraper metadata file.
+    ///
+    /// # Returns
+    /// The files written, or `None` if the overwrite policy kept an existing CSV.
+    pub(crate) async fn save_csv_to_disk(
+        &self,
+        filename: &str,
+        data: Result<Vec<Result<Vec<SearchResult>, ScraperError>>>,
+        scraper_metadata: Option<Value>,
+    ) -> Result<Option<SavedOutput>> {
+        let cleaned_data: Vec<SearchResult> =
+            data?.into_iter().filter_map(Result::ok).flatten().collect();
+        let file_content = csv_export::components_to_csv(&cleaned_data)?;
+
+        let component_filepath = self.output_dir().await.join(format!("{}.csv", filename));
+        let Some(component_filepath) = self
+            .save_to_file(&file_content, &component_filepath)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let metadata_filepath = self
+            .save_scraper_metadata(&component_filepath, scraper_metadata)
+            .await?;
+
+        print_info_message("Done!", true);
+        Ok(Some(SavedOutput {
+            filepath: component_filepath,
+            metadata_filepath,
+        }))
+    }
+
+    /// Stores the components of a category as a new snapshot in the SQLite database.
+    ///
+    /// The database replaces the per-category JSON files, so neither the components nor the
+    /// scraper metadata are written anywhere else.
+    ///
+    /// # Returns
+    /// The database, as the file the components were written to.
+    pub(crate) async fn save_to_database(
+        &self,
+        category_name: &str,
+        data: Result<Vec<Result<Vec<SearchResult>, ScraperError>>>,
+        scraper_metadata: Option<Value>,
+    ) -> Result<Option<SavedOutput>> {
+        let cleaned_data: Vec<SearchResult> =
+            data?.into_iter().filter_map(Result::ok).flatten().collect();
+        let database_path = {
+            let args = self.args.read().await;
+            args.database
+                .clone()
+                .unwrap_or_else(|| args.output_dir().join(DEFAULT_DATABASE_FILENAME))
+        };
+
+        println!();
+        print_info_message(
+            &format!(
+                "Writing {} parts to {}...",
+                cleaned_data.len(),
+                database_path.display()
+            ),
+            false,
+        );
+        let category_name = category_name.to_string();
+        let scraper_metadata = scraper_metadata.unwrap_or_default();
+        let filepath = database_path.clone();
+        let run_id = tokio::task::spawn_blocking(move || {
+            SqliteStore::open(&database_path)?.insert_run(
+                &category_name,
+                &cleaned_data,
+                &scraper_metadata,
+            )
+        })
+        .await??;
+        debug!("Saved scrape run {}", run_id);
+
+        print_info_message("Done!", true);
+        Ok(Some(SavedOutput {
+            filepath,
+            metadata_filepath: None,
+        }))
+    }
+
+    /// Moves the parts streamed by an `NdjsonWriter` into place and writes the files that go with
+    /// them.
+    ///
+    /// The Octopart metadata goes to `<name>_header.json` and the scraper metadata to
+    /// `<name>_metadata.json`. Both are written before the parts file is moved into place, so
+    /// whenever `<name>.ndjson` exists its header does too.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer the parts were streamed to.
+    /// * `octopart_metadata` - The Octopart search metadata, without results.
+    /// * `scraper_metadata` - The scraper's own metadata for the category.
+    ///
+    /// # Returns
+    /// The files written, or `None` if the overwrite policy kept an existing parts file, in which
+    /// case the parts are left in the writer's temporary file.
+    pub(crate) async fn save_ndjson_to_disk(
+        &self,
+        writer: NdjsonWriter,
+        octopart_metadata: Option<Value>,
+        scraper_metadata: Option<Value>,
+    ) -> Result<Option<SavedOutput>> {
+        let Some(parts_filepath) = self.resolve_filepath(writer.path()).await? else {
+            print_info_message(
+                &format!(
+                    "The parts were left in {}",
+                    writer.temporary_path().display()
+                ),
+                false,
             );
-            self.save_json_to_file(scraper_meta, &scraper_filepath)
-                .await?;
+            return Ok(None);
+        };
+
+        if let Some(octopart_meta) = octopart_metadata {
+            let header_filepath = Self::companion_filepath(&parts_filepath, HEADER_FILE_SUFFIX);
+            Self::write_json_file(&octopart_meta, &header_filepath).await?;
         }
+        let metadata_filepath = self
+            .save_scraper_metadata(&parts_filepath, scraper_metadata)
+            .await?;
+
+        println!();
+        print_info_message(
+            &format!("Moving parts to {}...", parts_filepath.display()),
+            false,
+        );
+        let (parts_written, duplicates_dropped) = writer.finish(&parts_filepath).await?;
+        print_info_message(
+            &format!(
+                "Wrote {} parts ({} duplicates dropped)",
+                parts_written, duplicates_dropped
+            ),
+            false,
+        );
 
         print_info_message("Done!", true);
-        Ok(())
+        Ok(Some(SavedOutput {
+            filepath: parts_filepath,
+            metadata_filepath,
+        }))
     }
 
-    async fn save_json_to_file(&self, file_content: Value, filepath: &str) -> Result<()> {
-        // Check and create data directory if not exists
-        fs::create_dir_all(DEFAULT_SAVE_DIR).await?;
+    /// Writes the scraper metadata next to the output at `filepath`, under the same (possibly
+    /// versioned) name.
+    ///
+    /// # Returns
+    /// The path of the metadata file, or `None` if there was no metadata to write.
+    async fn save_scraper_metadata(
+        &self,
+        filepath: &Path,
+        scraper_metadata: Option<Value>,
+    ) -> Result<Option<PathBuf>> {
+        let Some(scraper_meta) = scraper_metadata else {
+            return Ok(None);
+        };
+        let scraper_filepath = Self::companion_filepath(filepath, METADATA_FILE_SUFFIX);
+        Self::write_json_file(&scraper_meta, &scraper_filepath).await?;
+        Ok(Some(scraper_filepath))
+    }
 
-        // Check if file exists and prompt for overwrite
-        if Path::new(&filepath).exists() {
-            if Cli::prompt_user_for_file_overwrite() {
-            } else {
-                print_error_message(&"Aborted saving to disk.");
-                return Ok(());
-            }
-        }
+    /// Writes `file_content` to `filepath` as pretty-printed JSON, following the overwrite policy.
+    ///
+    /// # Returns
+    /// The path the file was written to, or `None` if it was not written.
+    async fn save_json_to_file(
+        &self,
+        file_content: Value,
+        filepath: &Path,
+    ) -> Result<Option<PathBuf>> {
+        self.save_to_file(
+            serde_json::to_string_pretty(&file_content)?.as_bytes(),
+            filepath,
+        )
+        .await
+    }
+
+    /// Writes `file_content` to `filepath`, following the overwrite policy.
+    ///
+    /// # Returns
+    /// The path the file was written to, or `None` if it was not written.
+    async fn save_to_file(&self, file_content: &[u8], filepath: &Path) -> Result<Option<PathBuf>> {
+        let Some(filepath) = self.resolve_filepath(filepath).await? else {
+            return Ok(None);
+        };
 
         // Write to disk
         println!();
-        print_info_message(&format!("Writing {} to disk...", filepath), false);
-        let mut file = File::create(filepath).await?;
-        file.write_all(serde_json::to_string_pretty(&file_content)?.as_bytes())
-            .await?;
+        print_info_message(&format!("Writing {} to disk...", filepath.display()), false);
+        Self::write_file(file_content, &filepath).await?;
+        Ok(Some(filepath))
+    }
+
+    /// Decides where a file meant for `filepath` is written, according to `--overwrite`.
+    ///
+    /// # Returns
+    /// `filepath` itself if it does not exist yet or may be overwritten, a versioned path next to
+    /// it with `--overwrite version`, or `None` if the existing file is kept.
+    async fn resolve_filepath(&self, filepath: &Path) -> Result<Option<PathBuf>> {
+        if !filepath.exists() {
+            return Ok(Some(filepath.to_path_buf()));
+        }
+
+        let args = self.args.read().await;
+        match args.overwrite.unwrap_or_default() {
+            OverwritePolicy::Prompt => {
+                if Cli::prompt_user_for_file_overwrite(&args, &filepath.display().to_string())? {
+                    Ok(Some(filepath.to_path_buf()))
+                } else {
+                    print_error_message(&"Aborted saving to disk.");
+                    Ok(None)
+                }
+            }
+            OverwritePolicy::Overwrite => Ok(Some(filepath.to_path_buf())),
+            OverwritePolicy::Skip => {
+                print_info_message(
+                    &format!("{} already exists; skipped saving", filepath.display()),
+                    false,
+                );
+                Ok(None)
+            }
+            OverwritePolicy::Version => Ok(Some(versioned_path(filepath, Utc::now()))),
+        }
+    }
+
+    /// The path of a file that accompanies the output at `filepath`, e.g.
+    /// `./data/mica_capacitors_metadata.json` for `./data/mica_capacitors.json`.
+    fn companion_filepath(filepath: &Path, suffix: &str) -> PathBuf {
+        let stem = filepath.file_stem().unwrap_or_default().to_string_lossy();
+        filepath.with_file_name(format!("{}_{}.json", stem, suffix))
+    }
+
+    /// Writes pretty-printed JSON to `filepath` without asking before overwriting it.
+    async fn write_json_file(file_content: &Value, filepath: &Path) -> Result<()> {
+        Self::write_file(
+            serde_json::to_string_pretty(file_content)?.as_bytes(),
+            filepath,
+        )
+        .await
+    }
+
+    /// Writes `file_content` to a temporary file next to `filepath` and renames it into place, so
+    /// `filepath` never holds a partial document.
+    async fn write_file(file_content: &[u8], filepath: &Path) -> Result<()> {
+        // Check and create the output directory if not exists
+        if let Some(dir) = filepath.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        let mut temporary_filepath = filepath.as_os_str().to_owned();
+        temporary_filepath.push(".tmp");
+        let mut file = File::create(&temporary_filepath).await?;
+        file.write_all(file_content).await?;
+        file.sync_all().await?;
+        fs::rename(&temporary_filepath, filepath).await?;
         Ok(())
     }
 
-    fn serialize(&self, data: Vec<Value>, metadata: &mut Option<Value>) -> Value {
+    /// The directory output files are written to.
+    async fn output_dir(&self) -> PathBuf {
+        self.args.read().await.output_dir().to_path_buf()
+    }
+
+    fn serialize(&self, data: Vec<SearchResult>, metadata: &mut Option<Value>) -> Value {
         if let Some(meta) = metadata.as_mut() {
             // Replace the contents of 'data/search/results' with 'data'
             if let Some(results) = meta.pointer_mut("/data/search/results") {
@@ -107,36 +392,309 @@ impl DataManager {
         }
     }
 
-    fn sanitize_filename(name: &str) -> String {
+    pub(crate) fn sanitize_filename(name: &str) -> String {
         name.to_lowercase()
             .chars()
             .map(|c| if c.is_alphanumeric() { c } else { '_' })
             .collect::<String>()
     }
 
+    /// Combines every `*_metadata.json` in the output directory into `scraper_metadata.json`, and
+    /// deletes them once it is written.
     pub async fn combine_metadata_files(&self) -> Result<()> {
-        let metadata_files = self.find_metadata_files().await?;
-        let combined_metadata = self.combine_files_contents(&metadata_files).await?;
+        let output_dir = self.output_dir().await;
+        let metadata_files = self.find_metadata_files(&output_dir).await?;
+        self.combine_metadata_filepaths(&metadata_files).await
+    }
 
-        self.save_json_to_file(
-            combined_metadata,
-            &format!("{}/scraper_{}.json", DEFAULT_SAVE_DIR, METADATA_FILE_SUFFIX),
-        )
-        .await?;
+    /// Combines the given metadata files into `scraper_metadata.json` in the output directory,
+    /// and deletes them once it is written.
+    ///
+    /// # Arguments
+    /// * `metadata_files` - The metadata files to combine, e.g. the ones written by this run.
+    pub(crate) async fn combine_metadata_filepaths(
+        &self,
+        metadata_files: &[PathBuf],
+    ) -> Result<()> {
+        let output_dir = self.output_dir().await;
+        let combined_metadata = self.combine_files_contents(metadata_files).await?;
+
+        let saved = self
+            .save_json_to_file(
+                combined_metadata,
+                &Self::combined_metadata_filepath(&output_dir),
+            )
+            .await?;
+
+        // Keep the individual files around if the combined file was not written.
+        if saved.is_some() {
+            for file_path in metadata_files {
+                fs::remove_file(file_path).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merges the CSV of every category in the output directory into `combined.csv`.
+    ///
+    /// CSVs that were not written by a scrape, e.g. `final.csv` from `process`, are left out.
+    /// Unlike `combine_metadata_files`, the per-category files are kept.
+    pub async fn combine_csv_files(&self) -> Result<()> {
+        let output_dir = self.output_dir().await;
+        let combined_filepath = output_dir.join(COMBINED_CSV_FILENAME);
+        // Earlier combines, including versioned ones, are not categories.
+        let combined_stem = combined_filepath.file_stem().unwrap_or_default();
+        let mut csv_files = Vec::new();
+        let mut dir = fs::read_dir(&output_dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.is_file()
+                && path.extension().unwrap_or_default() == "csv"
+                && !path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .starts_with(&*combined_stem.to_string_lossy())
+            {
+                csv_files.push(path);
+            }
+        }
+        // Sort so that rows come out in the same order on every run.
+        csv_files.sort();
+
+        let mut tables = Vec::with_capacity(csv_files.len());
+        for path in &csv_files {
+            let table = fs::read(path).await?;
+            if csv_export::has_part_columns(&table) {
+                tables.push(table);
+            } else {
+                debug!(
+                    "Skipping {}, which is not a scraped category",
+                    path.display()
+                );
+            }
+        }
+        self.save_combined_csv(&tables).await
+    }
+
+    /// Merges the given category CSVs into `combined.csv` in the output directory.
+    ///
+    /// # Arguments
+    /// * `csv_files` - The CSVs to merge, e.g. the ones written by this run.
+    pub(crate) async fn combine_csv_filepaths(&self, csv_files: &[PathBuf]) -> Result<()> {
+        let mut tables = Vec::with_capacity(csv_files.len());
+        for path in csv_files {
+            tables.push(fs::read(path).await?);
+        }
+        self.save_combined_csv(&tables).await
+    }
+
+    async fn save_combined_csv(&self, tables: &[Vec<u8>]) -> Result<()> {
+        let combined_filepath = self.output_dir().await.join(COMBINED_CSV_FILENAME);
+        let combined = csv_export::combine_csvs(tables)?;
+        self.save_to_file(&combined, &combined_filepath).await?;
+        Ok(())
+    }
+
+    /// Compares two outputs of the same category, e.g. last year's scrape and this year's, and
+    /// reports what changed.
+    ///
+    /// The report is printed as a summary and saved as JSON, with the summary next to it as
+    /// `.txt`.
+    ///
+    /// # Arguments
+    /// * `old_filepath` - The earlier `.json`, `.ndjson` or `.csv` output.
+    /// * `new_filepath` - The later output, in any of the same formats.
+    /// * `report_filepath` - Where the JSON report goes; defaults to
+    ///   `<output-dir>/diff_<old name>_<new name>.json`.
+    pub async fn diff_snapshots(
+        &self,
+        old_filepath: &Path,
+        new_filepath: &Path,
+        report_filepath: Option<&Path>,
+    ) -> Result<()> {
+        let old_parts = diff::load_parts(old_filepath).await?;
+        let new_parts = diff::load_parts(new_filepath).await?;
+        let report = SnapshotDiff::between(&old_parts, &new_parts);
+        let summary = report.summary(old_filepath, new_filepath);
+        println!();
+        print!("{}", summary);
 
-        for file_path in metadata_files {
-            fs::remove_file(file_path).await?;
+        let report_filepath = match report_filepath {
+            Some(report_filepath) => report_filepath.to_path_buf(),
+            None => self.output_dir().await.join(format!(
+                "diff_{}_{}.json",
+                old_filepath
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                new_filepath
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            )),
+        };
+        if let Some(report_filepath) = self
+            .save_json_to_file(serde_json::to_value(&report)?, &report_filepath)
+            .await?
+        {
+            Self::write_file(summary.as_bytes(), &report_filepath.with_extension("txt")).await?;
         }
+        Ok(())
+    }
+
+    /// Computes the processed columns of every part in `snapshots` and saves them as one CSV,
+    /// the table the web tool's data is generated from.
+    ///
+    /// Parts are merged by MPN across snapshots, see `MergedTable`. Ceramic and film capacitors
+    /// that could not be classified are summarized, and listed next to the CSV in
+    /// `<name>_unclassified.json`.
+    ///
+    /// # Arguments
+    /// * `snapshots` - The `.json` or `.ndjson` outputs to process, with the year each was
+    ///   scraped in.
+    /// * `ceramic_classes_filepath` - The rules mapping ceramic dielectrics to their class;
+    ///   defaults to the embedded `processor/ceramic_classes.csv`.
+    /// * `output_filepath` - Where the CSV goes; defaults to `<output-dir>/final.csv`.
+    pub async fn process_outputs(
+        &self,
+        snapshots: &[YearlySnapshot],
+        ceramic_classes_filepath: Option<&Path>,
+        output_filepath: Option<&Path>,
+    ) -> Result<()> {
+        let ceramic_classes = match ceramic_classes_filepath {
+            Some(ceramic_classes_filepath) => {
+                CeramicClasses::from_path(ceramic_classes_filepath).await?
+            }
+            None => CeramicClasses::default(),
+        };
+
+        let mut table = MergedTable::default();
+        let mut report = ClassificationReport::default();
+        let mut parts = 0;
+        for snapshot in snapshots {
+            let components = reader::read_components(&snapshot.path).await?;
+            debug!(
+                "Read {} components from {}",
+                components.len(),
+                snapshot.path.display()
+            );
+            for component in &components {
+                let classification = ceramic_classes.classify(component);
+                report.record(component, &classification);
+                table.add(
+                    snapshot.year,
+                    &metrics::derive_metrics(component, classification),
+                );
+            }
+            parts += components.len();
+        }
+        println!();
+        print!("{}", report.summary());
 
+        let csv = table.to_csv()?;
+        let output_filepath = match output_filepath {
+            Some(output_filepath) => output_filepath.to_path_buf(),
+            None => self.output_dir().await.join(PROCESSED_CSV_FILENAME),
+        };
+        if let Some(output_filepath) = self.save_to_file(&csv, &output_filepath).await? {
+            Self::write_json_file(
+                &serde_json::to_value(&report.unclassified)?,
+                &Self::companion_filepath(&output_filepath, UNCLASSIFIED_FILE_SUFFIX),
+            )
+            .await?;
+            print_info_message(
+                &format!(
+                    "Processed {} parts into {} rows of {}",
+                    parts,
+                    table.len(),
+                    output_filepath.display()
+                ),
+                false,
+            );
+        }
+        Ok(())
+    }
+
+    /// Loads a processed CSV into the Postgres table the web tool queries.
+    ///
+    /// The table is created from the web tool's columns metadata and its rows are replaced in
+    /// one transaction, keeping its privileges, see `PostgresLoader::load`.
+    ///
+    /// # Arguments
+    /// * `input_filepath` - The CSV written by `process` or `merge`; defaults to
+    ///   `<output-dir>/final.csv`.
+    /// * `postgres_url` - The connection string; defaults to the `PG*` environment variables.
+    /// * `schema` - The Postgres schema of the table.
+    /// * `table` - The table.
+    /// * `columns_filepath` - The columns metadata; defaults to the embedded `columns.json`.
+    pub async fn load_into_postgres(
+        &self,
+        input_filepath: Option<&Path>,
+        postgres_url: Option<&str>,
+        schema: &str,
+        table: &str,
+        columns_filepath: Option<&Path>,
+    ) -> Result<()> {
+        let input_filepath = match input_filepath {
+            Some(input_filepath) => input_filepath.to_path_buf(),
+            None => self.output_dir().await.join(PROCESSED_CSV_FILENAME),
+        };
+        let csv = fs::read(&input_filepath)
+            .await
+            .with_context(|| format!("Failed to read {}", input_filepath.display()))?;
+        let columns_metadata = match columns_filepath {
+            Some(columns_filepath) => fs::read_to_string(columns_filepath)
+                .await
+                .with_context(|| format!("Failed to read {}", columns_filepath.display()))?,
+            None => postgres::DEFAULT_COLUMNS_METADATA.to_string(),
+        };
+        let header = csv::Reader::from_reader(csv.as_slice()).headers()?.clone();
+        let table_schema = TableSchema::new(&columns_metadata, &header)
+            .with_context(|| format!("Cannot load {}", input_filepath.display()))?;
+        if !table_schema.skipped.is_empty() {
+            debug!(
+                "Not loading columns the web tool does not use: {}",
+                table_schema.skipped.join(", ")
+            );
+        }
+
+        let config = postgres::postgres_config(postgres_url)?;
+        let mut loader = PostgresLoader::connect(&config).await?;
+        println!();
+        print_info_message(
+            &format!(
+                "Loading {} into {}.{}...",
+                input_filepath.display(),
+                schema,
+                table
+            ),
+            false,
+        );
+        let rows = loader.load(schema, table, &table_schema, &csv).await?;
+        print_info_message(
+            &format!("Loaded {} rows into {}.{}", rows, schema, table),
+            false,
+        );
         Ok(())
     }
 
-    async fn find_metadata_files(&self) -> Result<Vec<PathBuf>> {
+    fn combined_metadata_filepath(output_dir: &Path) -> PathBuf {
+        output_dir.join(format!("scraper_{}.json", METADATA_FILE_SUFFIX))
+    }
+
+    async fn find_metadata_files(&self, output_dir: &Path) -> Result<Vec<PathBuf>> {
         let mut metadata_files = Vec::new();
-        let mut dir = fs::read_dir(DEFAULT_SAVE_DIR).await?;
+        let mut dir = fs::read_dir(output_dir).await?;
+        let combined_filepath = Self::combined_metadata_filepath(output_dir);
 
         while let Some(entry) = dir.next_entry().await? {
             let path = entry.path();
+            // Skip the output of a previous combine, otherwise it would be nested and deleted.
+            if path == combined_filepath {
+                continue;
+            }
             if path.is_file()
                 && path.extension().unwrap_or_default() == "json"
                 && path
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncWriteExt, BufWriter},
+    sync::Mutex,
+};
+
+use crate::batch_manager::{PartKey, SearchResult};
+
+/// Appends the parts of a category to a newline-delimited JSON file while the scrape runs.
+///
+/// Parts go to `<name>.ndjson.tmp` and are only moved to `<name>.ndjson`, or wherever the overwrite
+/// policy sends them, by `DataManager` once the scrape has finished, so a reader never sees a file
+/// that is still being written. Lines that were written stay written, which means a later copy of
+/// a part cannot be merged into an earlier one the way the JSON output does: the first copy is
+/// kept and the others are dropped.
+pub(crate) struct NdjsonWriter {
+    path: PathBuf,
+    temporary_path: PathBuf,
+    state: Mutex<NdjsonWriterState>,
+}
+
+struct NdjsonWriterState {
+    file: BufWriter<File>,
+    written_parts: HashSet<PartKey>,
+    duplicates_dropped: usize,
+}
+
+impl NdjsonWriter {
+    /// Starts a new parts file, replacing any left behind by an earlier run.
+    ///
+    /// # Arguments
+    /// * `path` - Where the finished file is meant to go.
+    pub(crate) async fn create(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let temporary_path = path.with_extension("ndjson.tmp");
+        let file = File::create(&temporary_path)
+            .await
+            .with_context(|| format!("Failed to create {}", temporary_path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            temporary_path,
+            state: Mutex::new(NdjsonWriterState {
+                file: BufWriter::new(file),
+                written_parts: HashSet::new(),
+                duplicates_dropped: 0,
+            }),
+        })
+    }
+
+    /// Writes a page of components, one per line, skipping parts that were already written.
+    pub(crate) async fn append(&self, components: &[SearchResult]) -> Result<()> {
+        let mut state = self.state.lock().await;
+        let mut buffer = Vec::new();
+        for component in components {
+            if state.written_parts.insert(component.part.key()) {
+                serde_json::to_writer(&mut buffer, component)?;
+                buffer.push(b'\n');
+            } else {
+                state.duplicates_dropped += 1;
+            }
+        }
+        state.file.write_all(&buffer).await?;
+        state.file.flush().await?;
+        Ok(())
+    }
+
+    /// The number of duplicate parts dropped so far.
+    pub(crate) async fn duplicates_dropped(&self) -> usize {
+        self.state.lock().await.duplicates_dropped
+    }
+
+    /// Flushes the parts written so far and moves them to `path`.
+    ///
+    /// # Arguments
+    /// * `path` - Where the finished file goes; usually `path()`, or a versioned path next to it.
+    ///
+    /// # Returns
+    /// The number of parts written and the number of duplicates dropped.
+    pub(crate) async fn finish(self, path: &Path) -> Result<(usize, usize)> {
+        let mut state = self.state.into_inner();
+        state.file.flush().await?;
+        state.file.into_inner().sync_all().await?;
+        fs::rename(&self.temporary_path, path)
+            .await
+            .with_context(|| format!("Failed to move parts to {}", path.display()))?;
+        Ok((state.written_parts.len(), state.duplicates_dropped))
+    }
+
+    /// The path the parts are meant to be moved to when the writer finishes.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path the parts are written to until the writer finishes.
+    pub(crate) fn temporary_path(&self) -> &Path {
+        &self.temporary_path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch_manager::{FakeComponent, SearchResult};
+
+    use super::NdjsonWriter;
+
+    fn component(id: usize) -> SearchResult {
+        FakeComponent::new(&id.to_string(), &format!("MC{:03}", id)).build()
+    }
+
+    #[tokio::test]
+    async fn moves_parts_into_place_only_when_finished() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mica_capacitors.ndjson");
+        let writer = NdjsonWriter::create(&path).await.unwrap();
+
+        writer.append(&[component(1), component(2)]).await.unwrap();
+        writer.append(&[component(2), component(3)]).await.unwrap();
+        assert!(!path.exists());
+        assert!(writer.temporary_path().exists());
+
+        assert_eq!(writer.finish(&path).await.unwrap(), (3, 1));
+        let content = std::fs::read_to_string(&path).unwrap();
+        let part_ids: Vec<_> = content
+            .lines()
+            .map(|line| serde_json::from_str::<SearchResult>(line).unwrap().part.id)
+            .collect();
+        assert_eq!(part_ids, ["1", "2", "3"]);
+        assert!(!path.with_extension("ndjson.tmp").exists());
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use tokio::fs;
+
+use crate::batch_manager::SearchResult;
+
+/// Reads the components of a category from a `.json` or `.ndjson` output written by
+/// `DataManager`.
+///
+/// JSON outputs may hold the full Octopart response, with the components under
+/// `data.search.results`, or only `results` when the metadata was missing.
+pub(crate) async fn read_components(path: &Path) -> Result<Vec<SearchResult>> {
+    let content = fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    match extension.as_ref() {
+        "json" => {
+            let json: Value = serde_json::from_slice(&content)?;
+            let results = json
+                .pointer("/data/search/results")
+                .or_else(|| json.get("results"))
+                .with_context(|| format!("{} holds no results", path.display()))?;
+            serde_json::from_value(results.clone())
+                .with_context(|| format!("Failed to read the parts in {}", path.display()))
+        }
+        "ndjson" => content
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_slice)
+            .collect::<Result<Vec<SearchResult>, _>>()
+            .with_context(|| format!("Failed to read the parts in {}", path.display())),
+        _ => bail!(
+            "Cannot read parts from {}; expected a .json or .ndjson output",
+            path.display()
+        ),
+    }
+}
@@ -0,0 +1,265 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use crate::batch_manager::SearchResult;
+use crate::config::categories::CATEGORIES_MAP;
+
+const SCHEMA: &str = r"
+CREATE TABLE IF NOT EXISTS categories (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS scrape_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_id TEXT REFERENCES categories (id),
+    category_name TEXT NOT NULL,
+    date_collected INTEGER NOT NULL,
+    components_scraped INTEGER,
+    components_missed INTEGER,
+    duplicates_dropped INTEGER,
+    total_time REAL,
+    metadata TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS parts (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES scrape_runs (id),
+    part_id TEXT NOT NULL,
+    mpn TEXT NOT NULL,
+    manufacturer_id TEXT NOT NULL,
+    manufacturer_name TEXT NOT NULL,
+    category_id TEXT,
+    description TEXT,
+    datasheet_url TEXT,
+    median_price_1000 REAL,
+    median_price_1000_currency TEXT,
+    UNIQUE (run_id, part_id, mpn, manufacturer_id)
+);
+
+CREATE TABLE IF NOT EXISTS specs (
+    part_row_id INTEGER NOT NULL REFERENCES parts (id),
+    attribute_shortname TEXT NOT NULL,
+    attribute_id TEXT NOT NULL,
+    attribute_name TEXT NOT NULL,
+    display_value TEXT NOT NULL,
+    PRIMARY KEY (part_row_id, attribute_shortname)
+);
+
+CREATE INDEX IF NOT EXISTS parts_mpn ON parts (mpn);
+";
+
+/// A SQLite database holding every scrape as a snapshot.
+///
+/// Each saved category becomes a row in `scrape_runs`, and its parts and specs are stored under
+/// that run's id, so earlier snapshots are never overwritten. `categories` mirrors
+/// `CATEGORIES_MAP`.
+///
+/// Parts get their own row id, which `specs` refers to, because a response may carry parts
+/// without an Octopart id; those are told apart by MPN and manufacturer.
+pub(crate) struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.execute_batch(SCHEMA)?;
+
+        let store = Self { connection };
+        store.update_categories()?;
+        Ok(store)
+    }
+
+    fn update_categories(&self) -> Result<()> {
+        let mut statement = self
+            .connection
+            .prepare("INSERT OR REPLACE INTO categories (id, name) VALUES (?1, ?2)")?;
+        for (name, id) in CATEGORIES_MAP.entries() {
+            statement.execute(params![id, name])?;
+        }
+        Ok(())
+    }
+
+    /// Stores the parts of a category as a new snapshot.
+    ///
+    /// Everything is written in one transaction, so a failed save leaves no partial run behind.
+    ///
+    /// # Arguments
+    /// * `category_name` - The category the parts belong to.
+    /// * `components` - The parts, without duplicates.
+    /// * `scraper_metadata` - The scraper's own metadata for the category, including
+    ///   `date_collected`.
+    ///
+    /// # Returns
+    /// The id of the new `scrape_runs` row.
+    pub(crate) fn insert_run(
+        &mut self,
+        category_name: &str,
+        components: &[SearchResult],
+        scraper_metadata: &Value,
+    ) -> Result<i64> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO scrape_runs (
+                category_id, category_name, date_collected, components_scraped,
+                components_missed, duplicates_dropped, total_time, metadata
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                CATEGORIES_MAP.get(category_name),
+                category_name,
+                scraper_metadata["date_collected"]
+                    .as_i64()
+                    .unwrap_or_default(),
+                scraper_metadata["components_scraped"].as_i64(),
+                scraper_metadata["components_missed"].as_i64(),
+                scraper_metadata["duplicates_dropped"].as_i64(),
+                scraper_metadata["total_time"].as_f64(),
+                scraper_metadata.to_string(),
+            ],
+        )?;
+        let run_id = transaction.last_insert_rowid();
+
+        {
+            let mut insert_part = transaction.prepare(
+                "INSERT INTO parts (
+                    run_id, part_id, mpn, manufacturer_id, manufacturer_name, category_id,
+                    description, datasheet_url, median_price_1000, median_price_1000_currency
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            let mut insert_spec = transaction.prepare(
+                "INSERT INTO specs (
+                    part_row_id, attribute_shortname, attribute_id, attribute_name, display_value
+                ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for component in components {
+                let part = &component.part;
+                let price = part.median_price_1000.as_ref();
+                insert_part.execute(params![
+                    run_id,
+                    part.id,
+                    part.mpn,
+                    part.manufacturer.id,
+                    part.manufacturer.name,
+                    part.category.as_ref().map(|category| &category.id),
+                    component.description,
+                    part.best_datasheet.as_ref().map(|datasheet| &datasheet.url),
+                    price.and_then(|price| price.converted_price),
+                    price.and_then(|price| price.converted_currency.as_ref()),
+                ])?;
+                let part_row_id = transaction.last_insert_rowid();
+                for spec in &part.specs {
+                    insert_spec.execute(params![
+                        part_row_id,
+                        spec.attribute.shortname,
+                        spec.attribute.id,
+                        spec.attribute.name,
+                        spec.display_value,
+                    ])?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+        Ok(run_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::SqliteStore;
+    use crate::batch_manager::{FakeComponent, SearchResult};
+
+    fn component(id: &str, capacitance: &str) -> SearchResult {
+        component_with_mpn(id, &format!("MC{}", id), capacitance)
+    }
+
+    fn component_with_mpn(id: &str, mpn: &str, capacitance: &str) -> SearchResult {
+        FakeComponent::new(id, mpn)
+            .with_category("6334")
+            .with_description("Mica capacitor")
+            .with_price(0.6)
+            .with_specs(&[("capacitance", capacitance)])
+            .build()
+    }
+
+    #[test]
+    fn stores_each_run_as_a_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("components.sqlite");
+
+        let mut store = SqliteStore::open(&path).unwrap();
+        let first_run = store
+            .insert_run(
+                "Mica Capacitors",
+                &[component("1", "10pF"), component("2", "22pF")],
+                &json!({ "date_collected": 1_700_000_000, "components_scraped": 2 }),
+            )
+            .unwrap();
+        drop(store);
+
+        let mut store = SqliteStore::open(&path).unwrap();
+        let second_run = store
+            .insert_run(
+                "Mica Capacitors",
+                &[component("1", "12pF")],
+                &json!({ "date_collected": 1_730_000_000, "components_scraped": 1 }),
+            )
+            .unwrap();
+        assert_ne!(first_run, second_run);
+
+        let connection = &store.connection;
+        let count = |sql: &str| -> i64 { connection.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM scrape_runs"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM parts"), 3);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM categories WHERE id = '6334' AND name = 'Mica Capacitors'"),
+            1
+        );
+        let latest_capacitance: String = connection
+            .query_row(
+                "SELECT specs.display_value FROM specs
+                 JOIN parts ON parts.id = specs.part_row_id
+                 JOIN scrape_runs ON scrape_runs.id = parts.run_id
+                 WHERE parts.part_id = '1' AND specs.attribute_shortname = 'capacitance'
+                 ORDER BY scrape_runs.date_collected DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(latest_capacitance, "12pF");
+    }
+
+    #[test]
+    fn stores_parts_without_an_octopart_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SqliteStore::open(&dir.path().join("components.sqlite")).unwrap();
+
+        store
+            .insert_run(
+                "Mica Capacitors",
+                &[
+                    component_with_mpn("", "MC10", "10pF"),
+                    component_with_mpn("", "MC22", "22pF"),
+                ],
+                &json!({ "date_collected": 1_700_000_000, "components_scraped": 2 }),
+            )
+            .unwrap();
+
+        let connection = &store.connection;
+        let count = |sql: &str| -> i64 { connection.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM parts WHERE part_id = ''"), 2);
+        assert_eq!(count("SELECT COUNT(DISTINCT part_row_id) FROM specs"), 2);
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::io;
+
+use tokio::task::JoinError;
+
+/// The ways a scrape can fail.
+///
+/// Request failures are split by what can be done about them: network failures and some HTTP
+/// statuses go away when the request is retried, a PerimeterX block needs a new PX key, and
+/// GraphQL errors or unknown names will fail the same way every time.
+#[derive(Debug)]
+pub enum ScraperError {
+    /// The request could not be sent or its response could not be read (timeouts, dropped or
+    /// refused connections).
+    Network(String),
+    /// Octopart answered with an unsuccessful HTTP status that was not a PerimeterX block.
+    HttpStatus(u16),
+    /// PerimeterX answered with a captcha page or block payload instead of data.
+    Blocked,
+    /// Octopart answered with a GraphQL `errors[]` payload; holds the error messages.
+    GraphQl(Vec<String>),
+    /// The response was not valid JSON or did not have the expected shape.
+    MalformedResponse(String),
+    /// With `--strict`, a response that parsed but had fields missing or not in the models; holds
+    /// one description per difference.
+    UnexpectedShape(Vec<String>),
+    /// A category name that is not in `CATEGORIES_MAP`.
+    UnknownCategory(String),
+    /// An attribute name or shortname that is not in `ATTRIBUTES_MAP`.
+    UnknownAttribute(String),
+    /// A PX key or user agent that cannot be sent as an HTTP header.
+    InvalidHeader(&'static str),
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A task panicked or was cancelled before it finished.
+    Task(String),
+}
+
+impl ScraperError {
+    /// Whether sending the same request again is likely to succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(_) => true,
+            Self::HttpStatus(status) => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Whether retrying the task that failed with this error may help.
+    ///
+    /// Transient errors may clear up, and a malformed response is often a one-off. Blocks are not
+    /// included: they are only worth retrying with a new PX key.
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient() || matches!(self, Self::MalformedResponse(_) | Self::Task(_))
+    }
+}
+
+impl fmt::Display for ScraperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(message) => write!(f, "Network error: {}", message),
+            Self::HttpStatus(status) => write!(f, "Octopart responded with HTTP {}", status),
+            Self::Blocked => write!(f, "The request was blocked by PerimeterX"),
+            Self::GraphQl(messages) => write!(f, "GraphQL error: {}", messages.join("; ")),
+            Self::MalformedResponse(message) => write!(f, "Malformed response: {}", message),
+            Self::UnexpectedShape(differences) => {
+                write!(f, "Unexpected response shape: {}", differences.join("; "))
+            }
+            Self::UnknownCategory(name) => write!(f, "Unknown category name: {}", name),
+            Self::UnknownAttribute(name) => write!(f, "Unknown attribute name: {}", name),
+            Self::InvalidHeader(header) => {
+                write!(
+                    f,
+                    "The {} contains characters not allowed in a header",
+                    header
+                )
+            }
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::Task(message) => write!(f, "Task failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScraperError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScraperError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<JoinError> for ScraperError {
+    fn from(error: JoinError) -> Self {
+        Self::Task(error.to_string())
+    }
+}
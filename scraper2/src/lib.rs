@@ -2,3 +2,5 @@ pub mod batch_manager;
 pub mod cli;
 pub mod config;
 pub mod data_manager;
+pub mod error;
+pub mod processor;
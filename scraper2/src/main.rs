@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
+use chrono::{Datelike, Utc};
 use scraper2::batch_manager::BatchManager;
-use scraper2::cli::Cli;
+use scraper2::cli::{Cli, Command, YearlySnapshot};
 use scraper2::config::constants::BATCH_SIZE;
 use scraper2::data_manager::DataManager;
 use tokio::sync::RwLock;
@@ -10,11 +11,67 @@ use tokio::sync::RwLock;
 async fn main() -> Result<(), anyhow::Error> {
     env_logger::init();
 
-    let args = Cli::prompt();
+    let mut args = Cli::prompt()?;
 
-    if args.combine_metadata {
+    if let Some(command) = args.command.take() {
         let data_manager = DataManager::new(Arc::new(RwLock::new(args)));
-        data_manager.combine_metadata_files().await?;
+        match command {
+            Command::Diff { old, new, report } => {
+                data_manager
+                    .diff_snapshots(&old, &new, report.as_deref())
+                    .await?;
+            }
+            Command::Process {
+                inputs,
+                year,
+                ceramic_classes,
+                output,
+            } => {
+                let year = year.unwrap_or_else(|| Utc::now().year());
+                let snapshots: Vec<_> = inputs
+                    .into_iter()
+                    .map(|path| YearlySnapshot { year, path })
+                    .collect();
+                data_manager
+                    .process_outputs(&snapshots, ceramic_classes.as_deref(), output.as_deref())
+                    .await?;
+            }
+            Command::Merge {
+                snapshots,
+                ceramic_classes,
+                output,
+            } => {
+                data_manager
+                    .process_outputs(&snapshots, ceramic_classes.as_deref(), output.as_deref())
+                    .await?;
+            }
+            Command::Load {
+                input,
+                postgres_url,
+                schema,
+                table,
+                columns,
+            } => {
+                data_manager
+                    .load_into_postgres(
+                        input.as_deref(),
+                        postgres_url.as_deref(),
+                        &schema,
+                        &table,
+                        columns.as_deref(),
+                    )
+                    .await?;
+            }
+        }
+    } else if args.combine_metadata || args.combine_csv {
+        let (combine_metadata, combine_csv) = (args.combine_metadata, args.combine_csv);
+        let data_manager = DataManager::new(Arc::new(RwLock::new(args)));
+        if combine_metadata {
+            data_manager.combine_metadata_files().await?;
+        }
+        if combine_csv {
+            data_manager.combine_csv_files().await?;
+        }
     } else {
         let mut batch_manager = BatchManager::new(args, BATCH_SIZE);
         batch_manager.run().await?;
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::batch_manager::SearchResult;
+use crate::config::categories::CATEGORIES_MAP;
+
+/// `processor/ceramic_classes.csv`, the rules used when no other table is given.
+const DEFAULT_CERAMIC_CLASSES: &str = include_str!("../../../processor/ceramic_classes.csv");
+
+/// Film dielectrics by the names Octopart gives them in `dielectricmaterial`, abbreviated as in
+/// https://en.wikipedia.org/wiki/Film_capacitor.
+const FILM_DIELECTRICS: &[(&str, &str)] = &[
+    ("Polyester", "PET"),
+    ("PET", "PET"),
+    ("Polypropylene", "PP"),
+    ("PP", "PP"),
+    ("Polyphenylene", "PPS"),
+    ("Polystyrene", "PS"),
+    ("Polyethylene", "PE"),
+];
+
+/// The class of a ceramic dielectric, if known, possibly different for some manufacturers.
+#[derive(Debug)]
+struct CeramicRule {
+    class: Option<String>,
+    /// `(manufacturer, class)` pairs, matched case-insensitively against the part's manufacturer.
+    overrides: Vec<(String, String)>,
+}
+
+/// Maps the EIA dielectric codes of ceramic capacitors, e.g. `X7R`, to their class (`C1`, `C2` or
+/// `C3`).
+///
+/// The table is a CSV whose rows are a dielectric and its class, optionally followed by
+/// manufacturer and class pairs for manufacturers that use the code differently. A dielectric
+/// may be listed without a class:
+///
+/// ```text
+/// Dielectric,Class,,
+/// X7R,C2,,
+/// E,C3,Kyocera,C1
+/// UX,,,
+/// ```
+#[derive(Debug)]
+pub(crate) struct CeramicClasses {
+    rules: HashMap<String, CeramicRule>,
+}
+
+impl Default for CeramicClasses {
+    /// The rules of `processor/ceramic_classes.csv`, embedded at compile time.
+    fn default() -> Self {
+        Self::from_csv(DEFAULT_CERAMIC_CLASSES.as_bytes())
+            .expect("processor/ceramic_classes.csv is a valid rules table")
+    }
+}
+
+impl CeramicClasses {
+    /// Parses a rules table.
+    ///
+    /// # Arguments
+    /// * `csv` - The table, with a header row.
+    pub(crate) fn from_csv(csv: &[u8]) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv);
+        let mut rules = HashMap::new();
+        for (index, record) in reader.records().enumerate() {
+            // The header is line 1.
+            let line = index + 2;
+            let record = record?;
+            let mut fields = record.iter().map(str::trim);
+            let dielectric = fields.next().unwrap_or_default();
+            if dielectric.is_empty() {
+                bail!("Line {} of the ceramic classes has no dielectric", line);
+            }
+            // Dielectrics whose class is unknown are listed without one.
+            let class = fields.next().filter(|class| !class.is_empty());
+
+            let fields: Vec<&str> = fields.collect();
+            let mut overrides = Vec::new();
+            for pair in fields.chunks(2) {
+                match pair {
+                    [manufacturer, class] if !manufacturer.is_empty() && !class.is_empty() => {
+                        overrides.push((manufacturer.to_string(), class.to_string()));
+                    }
+                    // Rows without overrides still have the empty columns of the header.
+                    ["", ""] | [""] => {}
+                    _ => bail!(
+                        "Line {} of the ceramic classes has a manufacturer without a class",
+                        line
+                    ),
+                }
+            }
+
+            let rule = CeramicRule {
+                class: class.map(str::to_string),
+                overrides,
+            };
+            if rules.insert(dielectric.to_string(), rule).is_some() {
+                bail!(
+                    "Line {} of the ceramic classes repeats the dielectric {:?}",
+                    line,
+                    dielectric
+                );
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// Reads a rules table from a file, see `CeramicClasses::from_csv`.
+    pub(crate) async fn from_path(path: &Path) -> Result<Self> {
+        let csv = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_csv(&csv).with_context(|| format!("Invalid ceramic classes {}", path.display()))
+    }
+
+    /// The class of a ceramic dielectric as made by `manufacturer`.
+    pub(crate) fn class(&self, dielectric: &str, manufacturer: &str) -> Option<&str> {
+        let rule = self.rules.get(dielectric.trim())?;
+        let manufacturer = manufacturer.to_lowercase();
+        match rule
+            .overrides
+            .iter()
+            .find(|(name, _)| manufacturer.contains(&name.to_lowercase()))
+        {
+            Some((_, class)) => Some(class),
+            None => rule.class.as_deref(),
+        }
+    }
+
+    /// Classifies a part by its category and specs.
+    pub(crate) fn classify(&self, component: &SearchResult) -> Classification {
+        let part = &component.part;
+        let category_id = part.category.as_ref().map(|category| category.id.as_str());
+        let display_value = |shortname: &str| {
+            part.specs
+                .iter()
+                .find(|spec| spec.attribute.shortname == shortname)
+                .map(|spec| spec.display_value.as_str())
+        };
+        let is = |name: &str| category_id.is_some() && category_id == category(name);
+
+        if is("Ceramic Capacitors") {
+            // The dielectric of a ceramic is its EIA code, which the class is looked up by.
+            let dielectric = display_value("dielectric");
+            Classification {
+                ceramic_class: dielectric
+                    .and_then(|dielectric| self.class(dielectric, &part.manufacturer.name))
+                    .map(str::to_string),
+                dielectric: dielectric.map(str::to_string),
+            }
+        } else if is("Film Capacitors") {
+            Classification {
+                ceramic_class: None,
+                dielectric: display_value("dielectricmaterial").and_then(|material| {
+                    FILM_DIELECTRICS
+                        .iter()
+                        .find(|(name, _)| *name == material)
+                        .map(|(_, dielectric)| dielectric.to_string())
+                }),
+            }
+        } else {
+            let dielectric = if is("Aluminum Electrolytic Capacitors") {
+                Some("aluminum")
+            } else if is("Tantalum Capacitors") {
+                Some("tantalum")
+            } else if is("Mica Capacitors") {
+                Some("mica")
+            } else {
+                None
+            };
+            Classification {
+                ceramic_class: None,
+                dielectric: dielectric.map(str::to_string),
+            }
+        }
+    }
+}
+
+fn category(name: &str) -> Option<&'static str> {
+    CATEGORIES_MAP.get(name).copied()
+}
+
+/// The `ceramic_class` and `dielectric` columns of a part, which the web tool's custom categories
+/// (`ceramic_class='C1'`, `dielectric='PP'`, ...) select by.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Classification {
+    pub(crate) ceramic_class: Option<String>,
+    pub(crate) dielectric: Option<String>,
+}
+
+/// A ceramic or film capacitor that is missing from the web tool's custom categories because it
+/// could not be classified.
+#[derive(Debug, Serialize)]
+pub(crate) struct Unclassified {
+    pub(crate) mpn: String,
+    pub(crate) manufacturer: String,
+    pub(crate) category: String,
+    /// The spec the class would come from, e.g. `dielectric`.
+    pub(crate) shortname: String,
+    /// Its value, or `None` when the part does not list it.
+    pub(crate) value: Option<String>,
+}
+
+/// The parts that could not be classified.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ClassificationReport {
+    pub(crate) classified: usize,
+    pub(crate) unclassified: Vec<Unclassified>,
+}
+
+impl ClassificationReport {
+    /// Records the classification of a part, noting ceramics without a class and films without
+    /// a dielectric.
+    pub(crate) fn record(&mut self, component: &SearchResult, classification: &Classification) {
+        let part = &component.part;
+        let category_id = part.category.as_ref().map(|category| category.id.as_str());
+        let (category_name, shortname) = if category_id == category("Ceramic Capacitors")
+            && classification.ceramic_class.is_none()
+        {
+            ("Ceramic Capacitors", "dielectric")
+        } else if category_id == category("Film Capacitors") && classification.dielectric.is_none()
+        {
+            ("Film Capacitors", "dielectricmaterial")
+        } else {
+            self.classified += 1;
+            return;
+        };
+
+        self.unclassified.push(Unclassified {
+            mpn: part.mpn.clone(),
+            manufacturer: part.manufacturer.name.clone(),
+            category: category_name.to_string(),
+            shortname: shortname.to_string(),
+            value: part
+                .specs
+                .iter()
+                .find(|spec| spec.attribute.shortname == shortname)
+                .map(|spec| spec.display_value.clone()),
+        });
+    }
+
+    /// A human readable summary, with the unclassified parts counted by the value that failed.
+    pub(crate) fn summary(&self) -> String {
+        let mut counts: BTreeMap<(&str, &str, Option<&str>), usize> = BTreeMap::new();
+        for part in &self.unclassified {
+            *counts
+                .entry((&part.category, &part.shortname, part.value.as_deref()))
+                .or_default() += 1;
+        }
+
+        let mut summary = String::new();
+        // Writing to a String cannot fail.
+        let _ = writeln!(
+            summary,
+            "Classified: {} parts, unclassified: {} parts",
+            self.classified,
+            self.unclassified.len()
+        );
+        for ((category, shortname, value), count) in counts {
+            let _ = writeln!(
+                summary,
+                "  {}, {} {}: {} parts",
+                category,
+                shortname,
+                value.map_or("missing".to_string(), |value| format!("{:?}", value)),
+                count
+            );
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CeramicClasses, ClassificationReport};
+    use crate::batch_manager::{FakeComponent, SearchResult};
+
+    fn component(category_id: &str, manufacturer: &str, specs: &[(&str, &str)]) -> SearchResult {
+        FakeComponent::new("1", "PART")
+            .with_category(category_id)
+            .with_manufacturer(manufacturer)
+            .with_specs(specs)
+            .build()
+    }
+
+    #[test]
+    fn classifies_with_the_embedded_table() {
+        let classes = CeramicClasses::default();
+        assert_eq!(classes.class("X7R", "Murata"), Some("C2"));
+        assert_eq!(classes.class("C0G, NP0", "TDK"), Some("C1"));
+        assert_eq!(classes.class("E", "Vishay"), Some("C3"));
+        assert_eq!(classes.class("E", "KYOCERA AVX"), Some("C1"));
+        assert_eq!(classes.class("UX", "Murata"), None);
+        assert_eq!(classes.class("Unobtainium", "Murata"), None);
+
+        let ceramic = classes.classify(&component("6332", "Kemet", &[("dielectric", "X5R")]));
+        assert_eq!(ceramic.ceramic_class.as_deref(), Some("C2"));
+        assert_eq!(ceramic.dielectric.as_deref(), Some("X5R"));
+        let film = classes.classify(&component(
+            "6333",
+            "Kemet",
+            &[("dielectricmaterial", "Polypropylene")],
+        ));
+        assert_eq!(film.ceramic_class, None);
+        assert_eq!(film.dielectric.as_deref(), Some("PP"));
+        let tantalum = classes.classify(&component("6336", "Kemet", &[]));
+        assert_eq!(tantalum.dielectric.as_deref(), Some("tantalum"));
+    }
+
+    #[test]
+    fn reports_unclassified_parts() {
+        let classes = CeramicClasses::from_csv(b"Dielectric,Class,,\nX7R,C2,,\n").unwrap();
+        let mut report = ClassificationReport::default();
+        for component in [
+            component("6332", "Kemet", &[("dielectric", "X7R")]),
+            component("6332", "Kemet", &[("dielectric", "Y5V")]),
+            component("6332", "Kemet", &[]),
+            component("6333", "Kemet", &[("dielectricmaterial", "Paper")]),
+            component("6334", "Kemet", &[]),
+        ] {
+            report.record(&component, &classes.classify(&component));
+        }
+
+        assert_eq!(report.classified, 2);
+        let values: Vec<_> = report
+            .unclassified
+            .iter()
+            .map(|part| (part.shortname.as_str(), part.value.as_deref()))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                ("dielectric", Some("Y5V")),
+                ("dielectric", None),
+                ("dielectricmaterial", Some("Paper")),
+            ]
+        );
+        assert!(report
+            .summary()
+            .contains("Film Capacitors, dielectricmaterial \"Paper\": 1 parts"));
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        for table in [
+            "Dielectric,Class\n,C2\n",
+            "Dielectric,Class\nX7R,C2\nX7R,C1\n",
+            "Dielectric,Class,,\nE,C3,Kyocera,\n",
+        ] {
+            assert!(
+                CeramicClasses::from_csv(table.as_bytes()).is_err(),
+                "{}",
+                table
+            );
+        }
+    }
+}
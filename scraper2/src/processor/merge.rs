@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context, Result};
+
+use super::metrics::{PartRow, COLUMNS, COLUMNS_THAT_UPDATE_YEARLY};
+
+/// A part merged across the years it was scraped in.
+#[derive(Debug)]
+struct MergedPart {
+    /// The most recent year the part was scraped in, whose values win.
+    year: i32,
+    /// The values of `COLUMNS`.
+    cells: Vec<String>,
+    /// The values of `COLUMNS_THAT_UPDATE_YEARLY` by year.
+    yearly_values: BTreeMap<i32, [Option<f64>; 2]>,
+}
+
+/// The processed table of one or more yearly scrapes, one row per MPN, as
+/// `processor/compute.py::merge` built it.
+///
+/// The static columns hold the most recent year's values, falling back to older years for values
+/// the most recent scrape does not have. The columns that update yearly are kept for every year,
+/// e.g. `price_2023` and `price_2024`, and every year has a flag column, e.g. `"2023"`, that is
+/// `True` for the parts scraped that year, which the web tool filters by.
+#[derive(Debug, Default)]
+pub(crate) struct MergedTable {
+    years: BTreeSet<i32>,
+    parts: BTreeMap<String, MergedPart>,
+}
+
+impl MergedTable {
+    /// Adds a part scraped in `year`, merging it with earlier rows of the same MPN.
+    ///
+    /// Of two rows from the same year, the one added first wins.
+    pub(crate) fn add(&mut self, year: i32, row: &PartRow) {
+        self.years.insert(year);
+        let mut cells = row.cells();
+        let yearly_values = row.yearly_values();
+
+        let Some(part) = self.parts.get_mut(&row.mpn) else {
+            self.parts.insert(
+                row.mpn.clone(),
+                MergedPart {
+                    year,
+                    cells,
+                    yearly_values: BTreeMap::from([(year, yearly_values)]),
+                },
+            );
+            return;
+        };
+
+        if year > part.year {
+            part.year = year;
+            std::mem::swap(&mut part.cells, &mut cells);
+        }
+        // `cells` now holds the values that lost, which only fill the gaps.
+        for (cell, older) in part.cells.iter_mut().zip(cells) {
+            if cell.is_empty() {
+                *cell = older;
+            }
+        }
+
+        // The Python pipeline kept the largest value of a year.
+        let values = part.yearly_values.entry(year).or_insert([None, None]);
+        for (value, other) in values.iter_mut().zip(yearly_values) {
+            *value = match (*value, other) {
+                (Some(value), Some(other)) => Some(value.max(other)),
+                (value, other) => value.or(other),
+            };
+        }
+    }
+
+    /// The number of parts, after merging.
+    pub(crate) fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// `COLUMNS`, the yearly columns of every year, then the flag column of every year.
+    pub(crate) fn header(&self) -> Vec<String> {
+        let mut header: Vec<String> = COLUMNS.iter().map(|column| column.to_string()).collect();
+        for year in &self.years {
+            header.extend(
+                COLUMNS_THAT_UPDATE_YEARLY
+                    .iter()
+                    .map(|column| format!("{}_{}", column, year)),
+            );
+        }
+        header.extend(self.years.iter().map(|year| year.to_string()));
+        header
+    }
+
+    /// Writes the table as CSV, sorted by MPN.
+    pub(crate) fn to_csv(&self) -> Result<Vec<u8>> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(self.header())?;
+        for part in self.parts.values() {
+            let mut record = part.cells.clone();
+            for year in &self.years {
+                let values = part.yearly_values.get(year).copied().unwrap_or_default();
+                record.extend(
+                    values
+                        .iter()
+                        .map(|value| value.map(|value| value.to_string()).unwrap_or_default()),
+                );
+            }
+            record.extend(self.years.iter().map(|year| {
+                if part.yearly_values.contains_key(year) {
+                    "True".to_string()
+                } else {
+                    "False".to_string()
+                }
+            }));
+            writer.write_record(record)?;
+        }
+        writer.into_inner().context("Failed to flush CSV")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::MergedTable;
+    use crate::processor::metrics::PartRow;
+
+    fn row(mpn: &str, dielectric: Option<&str>, capacitance: f64, price: f64) -> PartRow {
+        PartRow {
+            category: Some("6333".to_string()),
+            manufacturer: "Kemet".to_string(),
+            mpn: mpn.to_string(),
+            dielectric: dielectric.map(str::to_string),
+            capacitance: Some(capacitance),
+            price: Some(price),
+            ..PartRow::default()
+        }
+    }
+
+    fn records(table: &MergedTable) -> Vec<csv::StringRecord> {
+        let csv = table.to_csv().unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        reader.records().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn merges_years_by_mpn() {
+        let mut table = MergedTable::default();
+        table.add(2024, &row("R76", None, 2e-6, 0.5));
+        table.add(2023, &row("R76", Some("PP"), 1e-6, 0.4));
+        table.add(2023, &row("R60", Some("PET"), 1e-6, 0.3));
+        table.add(2023, &row("R60", Some("PET"), 1e-6, 0.35));
+
+        assert_eq!(table.len(), 2);
+        let header = table.header();
+        let column = |name: &str| header.iter().position(|column| column == name).unwrap();
+        assert_eq!(
+            &header[header.len() - 6..],
+            [
+                "price_2023",
+                "energy_per_cost_2023",
+                "price_2024",
+                "energy_per_cost_2024",
+                "2023",
+                "2024"
+            ]
+        );
+
+        let records = records(&table);
+        let (r60, r76) = (&records[0], &records[1]);
+        assert_eq!(&r60[column("price_2023")], "0.35");
+        assert_eq!(&r60[column("price_2024")], "");
+        assert_eq!(&r60[column("2023")], "True");
+        assert_eq!(&r60[column("2024")], "False");
+
+        // The 2024 capacitance wins, the dielectric missing from 2024 comes from 2023.
+        assert_eq!(&r76[column("capacitance")], "0.000002");
+        assert_eq!(&r76[column("dielectric")], "PP");
+        assert_eq!(&r76[column("price_2023")], "0.4");
+        assert_eq!(&r76[column("price_2024")], "0.5");
+        assert_eq!(&r76[column("2023")], "True");
+        assert_eq!(&r76[column("2024")], "True");
+    }
+
+    #[test]
+    fn produces_every_column_of_the_web_tool() {
+        let columns: Value = serde_json::from_str(include_str!(
+            "../../../boost/client/src/metadata/columns.json"
+        ))
+        .unwrap();
+        let mut table = MergedTable::default();
+        table.add(2024, &row("R76", Some("PP"), 1e-6, 0.4));
+        let header = table.header();
+
+        for column in columns["columns"].as_array().unwrap() {
+            let column = column["column"].as_str().unwrap();
+            if column == "category" {
+                continue;
+            }
+            assert!(
+                header
+                    .iter()
+                    .any(|name| name == column || *name == format!("{}_2024", column)),
+                "missing column {}",
+                column
+            );
+        }
+        assert_eq!(records(&table)[0].len(), header.len());
+    }
+}
@@ -0,0 +1,426 @@
+use std::f64::consts::PI;
+
+use super::classify::Classification;
+use super::units::parse_display_value;
+use crate::batch_manager::SearchResult;
+
+/// Fit of the density of a dielectric, `D = M / V = k * V_rated^alpha * C^beta`, taken from
+/// https://ieeexplore.ieee.org/document/9829957.
+struct PowerFit {
+    k: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+/// `dielectric_power_fit` from `processor/categories.py`, keyed by ceramic class or dielectric.
+const DIELECTRIC_POWER_FIT: &[(&str, PowerFit)] = &[
+    (
+        "C1",
+        PowerFit {
+            k: 11.67,
+            alpha: 0.05585,
+            beta: 0.0665,
+        },
+    ),
+    (
+        "C2",
+        PowerFit {
+            k: 8.406,
+            alpha: -0.0045,
+            beta: 0.0272,
+        },
+    ),
+    (
+        "PET",
+        PowerFit {
+            k: 1.175,
+            alpha: -0.0212,
+            beta: -0.0167,
+        },
+    ),
+    (
+        "PP",
+        PowerFit {
+            k: 0.934,
+            alpha: -0.0207,
+            beta: -0.0250,
+        },
+    ),
+    // Listed as "Through-hole Al-Elec" in the paper.
+    (
+        "aluminum",
+        PowerFit {
+            k: 1.296,
+            alpha: -0.0732,
+            beta: -0.0434,
+        },
+    ),
+    // Listed as "Molded Tantalum" in the paper.
+    (
+        "tantalum",
+        PowerFit {
+            k: 4.928,
+            alpha: 0.0482,
+            beta: 0.049,
+        },
+    ),
+];
+
+/// The columns of the processed table, in the order of `column_map` in `processor/categories.py`.
+pub(crate) const COLUMNS: &[&str] = &[
+    "category",
+    "manufacturer",
+    "mpn",
+    "ceramic_class",
+    "dielectric",
+    "capacitance",
+    "voltage",
+    "current",
+    "esr",
+    "esr_frequency",
+    "esr_frequency_low",
+    "esr_frequency_high",
+    "volume",
+    "length",
+    "width",
+    "height",
+    "diameter",
+    "mass",
+    "energy",
+    "power",
+    "volumetric_energy_density",
+    "gravimetric_energy_density",
+    "volumetric_power_density",
+    "gravimetric_power_density",
+];
+
+/// Columns whose values change from one yearly scrape to the next. They follow `COLUMNS`, named
+/// with the year of the scrape, e.g. `price_2024`, so merged years keep every value.
+pub(crate) const COLUMNS_THAT_UPDATE_YEARLY: &[&str] = &["price", "energy_per_cost"];
+
+/// The factor applied to the rated capacitance of class 2 ceramics, which lose capacitance under
+/// DC bias.
+const CLASS_2_CAPACITANCE_FACTOR: f64 = 0.6;
+
+/// Test frequencies up to this are mains ripple frequencies (50, 60, 100 or 120 Hz).
+const LOW_ESR_FREQUENCY_MAX: f64 = 120.0;
+/// Test frequencies from this on are switching frequencies.
+const HIGH_ESR_FREQUENCY_MIN: f64 = 10_000.0;
+
+/// The processed columns of one part, in base SI units, as `processor/compute.py` produced them.
+///
+/// `None` stands for a value the part's specs do not give, the `NaN` of the Python pipeline.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct PartRow {
+    pub(crate) category: Option<String>,
+    pub(crate) manufacturer: String,
+    pub(crate) mpn: String,
+    pub(crate) ceramic_class: Option<String>,
+    pub(crate) dielectric: Option<String>,
+    pub(crate) capacitance: Option<f64>,
+    pub(crate) voltage: Option<f64>,
+    pub(crate) current: Option<f64>,
+    pub(crate) esr: Option<f64>,
+    pub(crate) esr_frequency: Option<f64>,
+    pub(crate) esr_frequency_low: Option<f64>,
+    pub(crate) esr_frequency_high: Option<f64>,
+    pub(crate) price: Option<f64>,
+    pub(crate) volume: Option<f64>,
+    pub(crate) length: Option<f64>,
+    pub(crate) width: Option<f64>,
+    pub(crate) height: Option<f64>,
+    pub(crate) diameter: Option<f64>,
+    pub(crate) mass: Option<f64>,
+    pub(crate) energy: Option<f64>,
+    pub(crate) power: Option<f64>,
+    pub(crate) volumetric_energy_density: Option<f64>,
+    pub(crate) gravimetric_energy_density: Option<f64>,
+    pub(crate) volumetric_power_density: Option<f64>,
+    pub(crate) gravimetric_power_density: Option<f64>,
+    pub(crate) energy_per_cost: Option<f64>,
+}
+
+/// Computes the processed columns of a part from its specs.
+///
+/// Follows `processor/compute.py`: the rated values are picked from the first spec present, the
+/// volume treats parts with a diameter as cylinders and every other part as a box, the mass comes
+/// from `DIELECTRIC_POWER_FIT`, and energy, power, densities and energy per cost are derived from
+/// those.
+///
+/// # Arguments
+/// * `component` - The part.
+/// * `classification` - Its ceramic class and dielectric, which pick the fit for the mass.
+pub(crate) fn derive_metrics(component: &SearchResult, classification: Classification) -> PartRow {
+    let part = &component.part;
+    let spec = |shortname: &str| {
+        let spec = part
+            .specs
+            .iter()
+            .find(|spec| spec.attribute.shortname == shortname)?;
+        parse_display_value(&spec.display_value).ok()?.nominal()
+    };
+    let first_spec = |shortnames: &[&str]| shortnames.iter().find_map(|shortname| spec(shortname));
+
+    let mut row = PartRow {
+        category: part.category.as_ref().map(|category| category.id.clone()),
+        manufacturer: part.manufacturer.name.clone(),
+        mpn: part.mpn.clone(),
+        ceramic_class: classification.ceramic_class,
+        dielectric: classification.dielectric,
+        // Octopart does not list the rated DC voltage and ripple current under one name.
+        voltage: first_spec(&["voltagerating_dc_", "voltagerating", "voltage"]),
+        current: first_spec(&["ripplecurrent", "ripplecurrent_ac_"]),
+        esr: first_spec(&[
+            "esr_equivalentseriesresistance_",
+            "resistance",
+            "seriesresistance",
+        ]),
+        esr_frequency: spec("testfrequency"),
+        price: part
+            .median_price_1000
+            .as_ref()
+            .and_then(|price| price.converted_price),
+        ..PartRow::default()
+    };
+
+    row.capacitance = spec("capacitance").map(|capacitance| {
+        if row.ceramic_class.as_deref() == Some("C2") {
+            capacitance * CLASS_2_CAPACITANCE_FACTOR
+        } else {
+            capacitance
+        }
+    });
+    row.esr_frequency_low = row
+        .esr_frequency
+        .filter(|frequency| *frequency <= LOW_ESR_FREQUENCY_MAX);
+    row.esr_frequency_high = row
+        .esr_frequency
+        .filter(|frequency| *frequency >= HIGH_ESR_FREQUENCY_MIN);
+
+    // Volume. Anything with a diameter is a cylinder, standing on its height or else its length.
+    row.diameter = spec("diameter");
+    if let Some(diameter) = row.diameter {
+        row.height = spec("height").or_else(|| spec("length"));
+        row.volume = row
+            .height
+            .map(|height| PI * (diameter / 2.0).powi(2) * height);
+    } else {
+        row.length = spec("length");
+        row.width = spec("width");
+        // The smaller of the height and the seated height, then the thickness, then the depth.
+        row.height = match (spec("height"), spec("height_seated_max_")) {
+            (Some(height), Some(seated)) => Some(height.min(seated)),
+            (height, seated) => height
+                .or(seated)
+                .or_else(|| spec("thickness"))
+                .or_else(|| spec("depth")),
+        };
+        row.volume = match (row.length, row.width, row.height) {
+            (Some(length), Some(width), Some(height)) => Some(length * width * height),
+            _ => None,
+        };
+    }
+
+    // Mass. Ceramics are fit by class, other capacitors by dielectric.
+    let fit_name = match row.ceramic_class.as_deref() {
+        Some(class @ ("C1" | "C2")) => Some(class),
+        _ => row
+            .dielectric
+            .as_deref()
+            .filter(|dielectric| matches!(*dielectric, "PP" | "PET" | "aluminum" | "tantalum")),
+    };
+    let fit = fit_name.and_then(|fit_name| {
+        DIELECTRIC_POWER_FIT
+            .iter()
+            .find(|(name, _)| *name == fit_name)
+            .map(|(_, fit)| fit)
+    });
+    // Like the Python pipeline, only a rated voltage or current of exactly zero rules the fit out.
+    if row.voltage != Some(0.0) && row.current != Some(0.0) {
+        if let (Some(fit), Some(voltage), Some(capacitance), Some(volume)) =
+            (fit, row.voltage, row.capacitance, row.volume)
+        {
+            row.mass =
+                finite(fit.k * voltage.powf(fit.alpha) * capacitance.powf(fit.beta) * volume);
+        }
+    }
+
+    // Energy, E = CV²/2, and rated power, P = V * I_rms.
+    row.energy = match (row.capacitance, row.voltage) {
+        (Some(capacitance), Some(voltage)) if voltage != 0.0 => {
+            Some(0.5 * capacitance * voltage.powi(2))
+        }
+        _ => None,
+    };
+    row.power = row
+        .voltage
+        .zip(row.current)
+        .map(|(voltage, current)| voltage * current);
+
+    row.volumetric_energy_density = ratio(row.energy, row.volume);
+    row.gravimetric_energy_density = ratio(row.energy, row.mass);
+    row.volumetric_power_density = ratio(row.power, row.volume);
+    row.gravimetric_power_density = ratio(row.power, row.mass);
+    row.energy_per_cost = ratio(row.energy, row.price);
+    row
+}
+
+impl PartRow {
+    /// The values of `COLUMNS`, missing ones left empty.
+    pub(crate) fn cells(&self) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        let number = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+        vec![
+            text(&self.category),
+            self.manufacturer.clone(),
+            self.mpn.clone(),
+            text(&self.ceramic_class),
+            text(&self.dielectric),
+            number(self.capacitance),
+            number(self.voltage),
+            number(self.current),
+            number(self.esr),
+            number(self.esr_frequency),
+            number(self.esr_frequency_low),
+            number(self.esr_frequency_high),
+            number(self.volume),
+            number(self.length),
+            number(self.width),
+            number(self.height),
+            number(self.diameter),
+            number(self.mass),
+            number(self.energy),
+            number(self.power),
+            number(self.volumetric_energy_density),
+            number(self.gravimetric_energy_density),
+            number(self.volumetric_power_density),
+            number(self.gravimetric_power_density),
+        ]
+    }
+
+    /// The values of `COLUMNS_THAT_UPDATE_YEARLY`.
+    pub(crate) fn yearly_values(&self) -> [Option<f64>; 2] {
+        [self.price, self.energy_per_cost]
+    }
+}
+
+/// `numerator / denominator`, or `None` when either is missing or the denominator is zero.
+fn ratio(numerator: Option<f64>, denominator: Option<f64>) -> Option<f64> {
+    match (numerator, denominator) {
+        (Some(numerator), Some(denominator)) if denominator != 0.0 => {
+            finite(numerator / denominator)
+        }
+        _ => None,
+    }
+}
+
+fn finite(value: f64) -> Option<f64> {
+    value.is_finite().then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{derive_metrics, PartRow};
+    use crate::batch_manager::{FakeComponent, SearchResult};
+    use crate::processor::classify::{CeramicClasses, Classification};
+
+    fn component(category_id: &str, price: f64, specs: &[(&str, &str)]) -> SearchResult {
+        FakeComponent::new("1", "UVR1H100MDD")
+            .with_category(category_id)
+            .with_price(price)
+            .with_specs(specs)
+            .build()
+    }
+
+    fn classified_metrics(component: &SearchResult) -> PartRow {
+        derive_metrics(component, CeramicClasses::default().classify(component))
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("missing value");
+        assert!(
+            ((actual - expected) / expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn derives_cylindrical_electrolytic_metrics() {
+        let row = classified_metrics(&component(
+            "6331",
+            0.05,
+            &[
+                ("capacitance", "10 µF"),
+                ("voltagerating", "63 V"),
+                ("voltagerating_dc_", "50 V"),
+                ("ripplecurrent", "100 mA @ 120 Hz"),
+                ("esr_equivalentseriesresistance_", "3.5274Ohm @ 120Hz"),
+                ("testfrequency", "120 Hz"),
+                ("diameter", "5 mm"),
+                ("length", "11 mm"),
+            ],
+        ));
+
+        assert_eq!(row.dielectric.as_deref(), Some("aluminum"));
+        assert_eq!(row.voltage, Some(50.0));
+        assert_close(row.current, 0.1);
+        assert_close(row.esr, 3.5274);
+        assert_eq!(row.esr_frequency_low, Some(120.0));
+        assert_eq!(row.esr_frequency_high, None);
+        assert_eq!(row.length, None);
+        assert_close(row.height, 11e-3);
+        let volume = PI * 2.5e-3f64.powi(2) * 11e-3;
+        assert_close(row.volume, volume);
+        let mass = 1.296 * 50f64.powf(-0.0732) * 10e-6f64.powf(-0.0434) * volume;
+        assert_close(row.mass, mass);
+        let energy = 0.5 * 10e-6 * 50.0 * 50.0;
+        assert_close(row.energy, energy);
+        assert_close(row.power, 5.0);
+        assert_close(row.volumetric_energy_density, energy / volume);
+        assert_close(row.gravimetric_energy_density, energy / mass);
+        assert_close(row.volumetric_power_density, 5.0 / volume);
+        assert_close(row.gravimetric_power_density, 5.0 / mass);
+        assert_close(row.energy_per_cost, energy / 0.05);
+    }
+
+    #[test]
+    fn derives_rectangular_ceramic_metrics() {
+        let specs = [
+            ("capacitance", "100 nF"),
+            ("voltagerating_dc_", "16 V"),
+            ("dielectric", "X7R"),
+            ("length", "2 mm"),
+            ("width", "1.25 mm"),
+            ("height", "1.35 mm"),
+            ("height_seated_max_", "1.25 mm"),
+        ];
+        let row = classified_metrics(&component("6332", 0.0, &specs));
+
+        assert_eq!(row.ceramic_class.as_deref(), Some("C2"));
+        assert_eq!(row.dielectric.as_deref(), Some("X7R"));
+        assert_close(row.capacitance, 60e-9);
+        let volume = 2e-3 * 1.25e-3 * 1.25e-3;
+        assert_close(row.volume, volume);
+        assert_close(
+            row.mass,
+            8.406 * 16f64.powf(-0.0045) * 60e-9f64.powf(0.0272) * volume,
+        );
+        assert_eq!(row.power, None);
+        assert_eq!(row.energy_per_cost, None);
+
+        // Without a class there is no fit for the mass, and nothing derived from it.
+        let classification = Classification {
+            ceramic_class: None,
+            dielectric: Some("X7R".to_string()),
+        };
+        let row = derive_metrics(&component("6332", 0.0, &specs), classification);
+        assert_close(row.capacitance, 100e-9);
+        assert_eq!(row.mass, None);
+        assert_eq!(row.gravimetric_energy_density, None);
+        assert!(row.volumetric_energy_density.is_some());
+    }
+}
@@ -0,0 +1,7 @@
+//! Native ports of the Python `processor` pipeline, which turns scraped parts into the columns
+//! uploaded to the database.
+
+pub(crate) mod classify;
+pub(crate) mod merge;
+pub(crate) mod metrics;
+pub mod units;
@@ -0,0 +1,822 @@
+use std::fmt;
+
+/// The SI unit a parsed quantity is expressed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Farad,
+    Volt,
+    Ampere,
+    Ohm,
+    Hertz,
+    Meter,
+    Kilogram,
+    Watt,
+    Henry,
+    Second,
+    /// Temperatures are kept in degrees Celsius rather than kelvin, as every datasheet quotes them.
+    Celsius,
+    /// A temperature coefficient, e.g. `±30ppm/°C` becomes `3e-5` per degree Celsius.
+    PerCelsius,
+    /// Percentages and ppm, as fractions: `5%` becomes `0.05`.
+    Ratio,
+    /// A bare number.
+    Unitless,
+}
+
+impl Unit {
+    /// The symbol the unit is written with.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Farad => "F",
+            Unit::Volt => "V",
+            Unit::Ampere => "A",
+            Unit::Ohm => "Ω",
+            Unit::Hertz => "Hz",
+            Unit::Meter => "m",
+            Unit::Kilogram => "kg",
+            Unit::Watt => "W",
+            Unit::Henry => "H",
+            Unit::Second => "s",
+            Unit::Celsius => "°C",
+            Unit::PerCelsius => "/°C",
+            Unit::Ratio | Unit::Unitless => "",
+        }
+    }
+}
+
+/// A number in an SI unit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.symbol())
+    }
+}
+
+/// The tolerance written after a value, e.g. the `±10%` of `100nF ±10%`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// A fraction of the value.
+    Relative(f64),
+    /// An amount in the value's unit.
+    Absolute(Quantity),
+}
+
+/// One side of a part, e.g. the `0.594" L` of `0.189" Dia x 0.594" L (4.80mm x 15.10mm)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dimension {
+    /// What the side is, e.g. `Dia`, `L`, `W` or `H`, when the display value says.
+    pub label: Option<String>,
+    /// The length in meters.
+    pub length: f64,
+}
+
+/// A parsed Octopart `display_value`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spec {
+    /// A single value, e.g. `100 nF`, `100nF ±10%` or `100 mA @ 120 Hz`.
+    Value {
+        quantity: Quantity,
+        tolerance: Option<Tolerance>,
+        /// The quantities the value was measured at, e.g. the `120 Hz` of `100 mA @ 120 Hz`.
+        conditions: Vec<Quantity>,
+    },
+    /// A symmetric tolerance on its own, e.g. `±20%`.
+    PlusMinus(Quantity),
+    /// A range, e.g. `-55°C ~ 125°C`, or an asymmetric tolerance such as `-20%, +80%`.
+    Range { min: Quantity, max: Quantity },
+    /// The outline of a part, e.g. `0.189" Dia x 0.594" L (4.80mm x 15.10mm)`.
+    Dimensions(Vec<Dimension>),
+}
+
+impl Spec {
+    /// The single number the spec stands for, if it has one: the value of a `Value`, or the
+    /// magnitude of a `PlusMinus`.
+    pub fn nominal(&self) -> Option<f64> {
+        match self {
+            Spec::Value { quantity, .. } | Spec::PlusMinus(quantity) => Some(quantity.value),
+            Spec::Range { .. } | Spec::Dimensions(_) => None,
+        }
+    }
+}
+
+/// A display value that is not a quantity, e.g. `Radial` or `Through Hole`.
+#[derive(Debug, PartialEq)]
+pub struct UnitParseError {
+    pub display_value: String,
+    pub reason: String,
+}
+
+impl fmt::Display for UnitParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot parse {:?}: {}", self.display_value, self.reason)
+    }
+}
+
+impl std::error::Error for UnitParseError {}
+
+/// A unit as it may be written, and how to turn a value written with it into its SI unit:
+/// `si = value * scale + offset`.
+struct UnitSymbol {
+    symbol: &'static str,
+    unit: Unit,
+    scale: f64,
+    offset: f64,
+    /// Whether SI prefixes may be put in front of the symbol.
+    prefixable: bool,
+}
+
+const fn symbol(symbol: &'static str, unit: Unit, scale: f64, prefixable: bool) -> UnitSymbol {
+    UnitSymbol {
+        symbol,
+        unit,
+        scale,
+        offset: 0.0,
+        prefixable,
+    }
+}
+
+const INCH: f64 = 0.0254;
+
+/// Every unit symbol found in Octopart display values. Symbols are matched exactly before any
+/// prefix is tried, so `m` is a meter, `mil` a thousandth of an inch and `h` an hour.
+const UNIT_SYMBOLS: &[UnitSymbol] = &[
+    symbol("F", Unit::Farad, 1.0, true),
+    symbol("V", Unit::Volt, 1.0, true),
+    symbol("VAC", Unit::Volt, 1.0, true),
+    symbol("VDC", Unit::Volt, 1.0, true),
+    symbol("Vac", Unit::Volt, 1.0, true),
+    symbol("Vdc", Unit::Volt, 1.0, true),
+    symbol("A", Unit::Ampere, 1.0, true),
+    symbol("Ω", Unit::Ohm, 1.0, true),
+    symbol("\u{2126}", Unit::Ohm, 1.0, true),
+    symbol("Ohm", Unit::Ohm, 1.0, true),
+    symbol("Ohms", Unit::Ohm, 1.0, true),
+    symbol("ohm", Unit::Ohm, 1.0, true),
+    symbol("ohms", Unit::Ohm, 1.0, true),
+    symbol("Hz", Unit::Hertz, 1.0, true),
+    symbol("m", Unit::Meter, 1.0, true),
+    symbol("\"", Unit::Meter, INCH, false),
+    symbol("in", Unit::Meter, INCH, false),
+    symbol("inch", Unit::Meter, INCH, false),
+    symbol("inches", Unit::Meter, INCH, false),
+    symbol("mil", Unit::Meter, INCH / 1000.0, false),
+    symbol("mils", Unit::Meter, INCH / 1000.0, false),
+    symbol("ft", Unit::Meter, 12.0 * INCH, false),
+    symbol("g", Unit::Kilogram, 1e-3, true),
+    symbol("lb", Unit::Kilogram, 0.453_592_37, false),
+    symbol("lbs", Unit::Kilogram, 0.453_592_37, false),
+    symbol("oz", Unit::Kilogram, 0.028_349_523_125, false),
+    symbol("W", Unit::Watt, 1.0, true),
+    symbol("H", Unit::Henry, 1.0, true),
+    symbol("s", Unit::Second, 1.0, true),
+    symbol("h", Unit::Second, 3600.0, false),
+    symbol("hr", Unit::Second, 3600.0, false),
+    symbol("hrs", Unit::Second, 3600.0, false),
+    symbol("Hr", Unit::Second, 3600.0, false),
+    symbol("Hrs", Unit::Second, 3600.0, false),
+    symbol("hours", Unit::Second, 3600.0, false),
+    symbol("°C", Unit::Celsius, 1.0, false),
+    symbol("℃", Unit::Celsius, 1.0, false),
+    UnitSymbol {
+        symbol: "°F",
+        unit: Unit::Celsius,
+        scale: 5.0 / 9.0,
+        offset: -32.0 * 5.0 / 9.0,
+        prefixable: false,
+    },
+    UnitSymbol {
+        symbol: "K",
+        unit: Unit::Celsius,
+        scale: 1.0,
+        offset: -273.15,
+        prefixable: false,
+    },
+    symbol("%", Unit::Ratio, 1e-2, false),
+    symbol("ppm", Unit::Ratio, 1e-6, false),
+    symbol("ppm/°C", Unit::PerCelsius, 1e-6, false),
+    symbol("ppm/K", Unit::PerCelsius, 1e-6, false),
+    symbol("%/°C", Unit::PerCelsius, 1e-2, false),
+];
+
+/// SI prefixes, with `da` before `d` so the longer one wins.
+const PREFIXES: &[(&str, f64)] = &[
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+];
+
+/// Parses an Octopart spec `display_value` into SI quantities.
+///
+/// Unlike `processor/compute.py::_convert_to_base_units`, which looks for prefix letters anywhere
+/// in the string, the unit is read as a whole and must be one of the known symbols, optionally
+/// behind an SI prefix, so `mm` is a millimeter, `m` a meter and `mOhm` a milliohm.
+///
+/// # Arguments
+/// * `display_value` - The display value, e.g. `100 nF`, `±10%`, `-55°C ~ 125°C`,
+///   `100 mA @ 120 Hz` or `0.189" Dia x 0.594" L (4.80mm x 15.10mm)`.
+///
+/// # Returns
+/// The parsed spec, or an error for display values that are not quantities, e.g. `Radial` or the
+/// zero-padded case code `0805`.
+pub fn parse_display_value(display_value: &str) -> Result<Spec, UnitParseError> {
+    let error = |reason: &str| UnitParseError {
+        display_value: display_value.to_string(),
+        reason: reason.to_string(),
+    };
+    let normalized = display_value.trim().replace("+/-", "±");
+    if normalized.is_empty() {
+        return Err(error("empty"));
+    }
+    // A zero-padded integer such as `0805` is a code, e.g. an EIA case size, not a number.
+    if normalized.len() > 1
+        && normalized.starts_with('0')
+        && normalized.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return Err(error("a code, not a number"));
+    }
+
+    if normalized.contains(" x ") || normalized.contains('(') {
+        return parse_dimensions(&normalized).ok_or_else(|| error("not a list of dimensions"));
+    }
+
+    let mut segments = normalized.split('@');
+    let head = segments.next().unwrap_or_default().trim();
+    let mut conditions = Vec::new();
+    for segment in segments {
+        conditions.extend(parse_quantities(segment).ok_or_else(|| error("bad condition"))?);
+    }
+    let conditions_allowed = |spec: Spec| {
+        if conditions.is_empty() {
+            Ok(spec)
+        } else {
+            Err(error("conditions are only supported on single values"))
+        }
+    };
+
+    if let Some(magnitude) = head.strip_prefix('±') {
+        let quantity = parse_quantity(magnitude, None).ok_or_else(|| error("bad tolerance"))?;
+        return conditions_allowed(Spec::PlusMinus(quantity));
+    }
+    if let Some((value, tolerance)) = head.split_once('±') {
+        let quantity = parse_quantity(value, None).ok_or_else(|| error("bad value"))?;
+        let tolerance = parse_quantity(tolerance, None).ok_or_else(|| error("bad tolerance"))?;
+        let tolerance = if tolerance.unit == Unit::Ratio {
+            Tolerance::Relative(tolerance.value)
+        } else {
+            Tolerance::Absolute(tolerance)
+        };
+        return Ok(Spec::Value {
+            quantity,
+            tolerance: Some(tolerance),
+            conditions,
+        });
+    }
+    if let Some((min, max)) = head.split_once('~').or_else(|| head.split_once(" to ")) {
+        return conditions_allowed(parse_range(min, max).ok_or_else(|| error("bad range"))?);
+    }
+    if let Some((first, second)) = head.split_once(',') {
+        // Asymmetric tolerances, e.g. `-20%, +80%`.
+        let is_signed = |text: &str| text.trim().starts_with(['-', '+']);
+        if is_signed(first) && is_signed(second) {
+            return conditions_allowed(
+                parse_range(first, second).ok_or_else(|| error("bad range"))?,
+            );
+        }
+    }
+
+    let mut quantities = parse_quantities(head).ok_or_else(|| error("not a quantity"))?;
+    // In `120Hz 2000 Hrs @ 85°C` the lifetime is the last quantity and the rest are conditions.
+    let quantity = quantities.pop().ok_or_else(|| error("not a quantity"))?;
+    quantities.extend(conditions);
+    Ok(Spec::Value {
+        quantity,
+        tolerance: None,
+        conditions: quantities,
+    })
+}
+
+/// Parses the two ends of a range, giving a bare number the unit of the other end, as in
+/// `-55 ~ 125°C`.
+fn parse_range(min: &str, max: &str) -> Option<Spec> {
+    let (_, min_unit) = split_number(min.trim())?;
+    let (_, max_unit) = split_number(max.trim())?;
+    let (min_unit, max_unit) = (min_unit.trim(), max_unit.trim());
+    let min_quantity = parse_quantity(min, (min_unit.is_empty()).then_some(max_unit))?;
+    let max_quantity = parse_quantity(max, (max_unit.is_empty()).then_some(min_unit))?;
+    if min_quantity.unit != max_quantity.unit {
+        return None;
+    }
+    let (min, max) = if min_quantity.value <= max_quantity.value {
+        (min_quantity, max_quantity)
+    } else {
+        (max_quantity, min_quantity)
+    };
+    Some(Spec::Range { min, max })
+}
+
+/// Parses outlines such as `0.189" Dia x 0.594" L (4.80mm x 15.10mm)`, `0.197" Dia (5.00mm)` or
+/// `10.00mm x 10.00mm`.
+///
+/// When the metric equivalent is given in parentheses its lengths are used, as they are what the
+/// datasheet states; the labels come from the part before it.
+fn parse_dimensions(text: &str) -> Option<Spec> {
+    let (outline, metric) = match text.split_once('(') {
+        Some((outline, metric)) => (outline, Some(metric.strip_suffix(')')?)),
+        None => (text, None),
+    };
+    let mut dimensions = outline
+        .split(" x ")
+        .map(parse_dimension)
+        .collect::<Option<Vec<_>>>()?;
+    if let Some(metric) = metric {
+        let metric = metric
+            .split(" x ")
+            .map(parse_dimension)
+            .collect::<Option<Vec<_>>>()?;
+        if metric.len() != dimensions.len() {
+            return None;
+        }
+        for (dimension, metric) in dimensions.iter_mut().zip(metric) {
+            dimension.length = metric.length;
+        }
+    }
+    Some(Spec::Dimensions(dimensions))
+}
+
+/// Parses one side of an outline, e.g. `0.594" L` or `15.10mm`.
+fn parse_dimension(text: &str) -> Option<Dimension> {
+    let (number, rest) = split_number(text.trim())?;
+    let (symbol, label) = match rest.split_once(char::is_whitespace) {
+        Some((symbol, label)) => (symbol, Some(label.trim().to_string())),
+        None => (rest, None),
+    };
+    let quantity = apply_unit(number, symbol)?;
+    if quantity.unit != Unit::Meter {
+        return None;
+    }
+    Some(Dimension {
+        label: label.filter(|label| !label.is_empty()),
+        length: quantity.value,
+    })
+}
+
+/// Parses a sequence of quantities such as `120Hz 2000 Hrs`, where a unit may or may not be
+/// separated from its number by a space.
+fn parse_quantities(text: &str) -> Option<Vec<Quantity>> {
+    let mut quantities = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let (number, after_number) = split_number(rest)?;
+        let after_number = after_number.trim_start();
+        let starts_number = |text: &str| {
+            text.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'))
+        };
+        let (symbol, after_symbol) = if starts_number(after_number) {
+            ("", after_number)
+        } else {
+            after_number
+                .split_once(char::is_whitespace)
+                .unwrap_or((after_number, ""))
+        };
+        quantities.push(apply_unit(number, symbol)?);
+        rest = after_symbol.trim_start();
+    }
+    Some(quantities)
+}
+
+/// Parses a number followed by an optional unit, e.g. `100 nF`, `6.3V` or `5 %`.
+///
+/// # Arguments
+/// * `text` - The quantity.
+/// * `default_symbol` - The unit to use when `text` is a bare number.
+fn parse_quantity(text: &str, default_symbol: Option<&str>) -> Option<Quantity> {
+    let (number, symbol) = split_number(text.trim())?;
+    let symbol = symbol.trim();
+    match (symbol.is_empty(), default_symbol) {
+        (true, Some(default_symbol)) => apply_unit(number, default_symbol),
+        _ => apply_unit(number, symbol),
+    }
+}
+
+/// Converts `number` written in the unit `symbol` to its SI unit.
+fn apply_unit(number: f64, symbol: &str) -> Option<Quantity> {
+    if symbol.is_empty() {
+        return Some(Quantity {
+            value: number,
+            unit: Unit::Unitless,
+        });
+    }
+    if let Some(unit) = UNIT_SYMBOLS.iter().find(|unit| unit.symbol == symbol) {
+        return Some(Quantity {
+            value: number * unit.scale + unit.offset,
+            unit: unit.unit,
+        });
+    }
+    PREFIXES.iter().find_map(|(prefix, factor)| {
+        let unit_symbol = symbol.strip_prefix(prefix)?;
+        let unit = UNIT_SYMBOLS
+            .iter()
+            .find(|unit| unit.prefixable && unit.symbol == unit_symbol)?;
+        Some(Quantity {
+            value: number * factor * unit.scale,
+            unit: unit.unit,
+        })
+    })
+}
+
+/// Splits a leading number, e.g. `-55` in `-55°C`, from what follows it.
+///
+/// Commas are read as thousands separators when three digits follow them, so `1,000 µF` is a
+/// thousand microfarads.
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim_start();
+    let mut digits = String::new();
+    let mut end = 0;
+    let mut chars = text.char_indices().peekable();
+    if let Some(&(_, sign)) = chars.peek() {
+        if matches!(sign, '-' | '+' | '−') {
+            digits.push(if sign == '+' { '+' } else { '-' });
+            chars.next();
+            end = sign.len_utf8();
+        }
+    }
+    for (index, c) in chars {
+        if c.is_ascii_digit() || c == '.' {
+            digits.push(c);
+            end = index + 1;
+        } else if c == ','
+            && is_thousands_group(&text[index + 1..])
+            && digits.ends_with(|c: char| c.is_ascii_digit())
+        {
+            end = index + 1;
+        } else {
+            break;
+        }
+    }
+    let number = digits.parse().ok()?;
+    Some((number, &text[end..]))
+}
+
+/// Whether `text` starts with exactly three digits, as after a thousands separator.
+fn is_thousands_group(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 3
+        && bytes[..3].iter().all(u8::is_ascii_digit)
+        && bytes.get(3).is_none_or(|byte| !byte.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_display_value, Dimension, Quantity, Spec, Tolerance, Unit};
+
+    fn assert_close(actual: f64, expected: f64, display_value: &str) {
+        let scale = expected.abs().max(1e-30);
+        assert!(
+            ((actual - expected) / scale).abs() < 1e-9,
+            "{:?}: expected {}, got {}",
+            display_value,
+            expected,
+            actual
+        );
+    }
+
+    fn value(display_value: &str) -> (Quantity, Option<Tolerance>, Vec<Quantity>) {
+        match parse_display_value(display_value) {
+            Ok(Spec::Value {
+                quantity,
+                tolerance,
+                conditions,
+            }) => (quantity, tolerance, conditions),
+            other => panic!("{:?}: expected a value, got {:?}", display_value, other),
+        }
+    }
+
+    #[test]
+    fn parses_single_values() {
+        let cases: &[(&str, f64, Unit)] = &[
+            // Capacitance
+            ("100 nF", 100e-9, Unit::Farad),
+            ("100nF", 100e-9, Unit::Farad),
+            ("10pF", 10e-12, Unit::Farad),
+            ("9.1 pF", 9.1e-12, Unit::Farad),
+            ("820 pF", 820e-12, Unit::Farad),
+            ("1nF", 1e-9, Unit::Farad),
+            ("4.7 µF", 4.7e-6, Unit::Farad),
+            ("4.7 μF", 4.7e-6, Unit::Farad),
+            ("22 uF", 22e-6, Unit::Farad),
+            ("1,000 µF", 1e-3, Unit::Farad),
+            ("1 F", 1.0, Unit::Farad),
+            ("100 mF", 0.1, Unit::Farad),
+            // Voltage
+            ("6.3 V", 6.3, Unit::Volt),
+            ("100V", 100.0, Unit::Volt),
+            ("630 V", 630.0, Unit::Volt),
+            ("275 VAC", 275.0, Unit::Volt),
+            ("1 kV", 1e3, Unit::Volt),
+            ("2kVDC", 2e3, Unit::Volt),
+            // Current
+            ("1.2 A", 1.2, Unit::Ampere),
+            ("160 mA", 0.16, Unit::Ampere),
+            ("10 µA", 10e-6, Unit::Ampere),
+            // Resistance
+            ("120 mOhms", 0.12, Unit::Ohm),
+            ("800mOhm", 0.8, Unit::Ohm),
+            ("3.5274Ohm", 3.5274, Unit::Ohm),
+            ("10 kΩ", 1e4, Unit::Ohm),
+            ("10 k\u{2126}", 1e4, Unit::Ohm),
+            ("1 MΩ", 1e6, Unit::Ohm),
+            // Frequency
+            ("120 Hz", 120.0, Unit::Hertz),
+            ("100 kHz", 1e5, Unit::Hertz),
+            ("1 MHz", 1e6, Unit::Hertz),
+            // Lengths
+            ("8 mm", 8e-3, Unit::Meter),
+            ("6.5024 mm", 6.5024e-3, Unit::Meter),
+            ("800 µm", 800e-6, Unit::Meter),
+            ("1.5 cm", 1.5e-2, Unit::Meter),
+            ("2 m", 2.0, Unit::Meter),
+            ("0.197\"", 0.197 * 0.0254, Unit::Meter),
+            ("0.5 in", 0.5 * 0.0254, Unit::Meter),
+            ("40 mil", 40.0 * 0.0254e-3, Unit::Meter),
+            // Temperature
+            ("105 °C", 105.0, Unit::Celsius),
+            ("-55 °C", -55.0, Unit::Celsius),
+            ("-40°C", -40.0, Unit::Celsius),
+            ("125℃", 125.0, Unit::Celsius),
+            ("257 °F", 125.0, Unit::Celsius),
+            ("298.15 K", 25.0, Unit::Celsius),
+            // Percentages and ppm
+            ("5 %", 0.05, Unit::Ratio),
+            ("20%", 0.2, Unit::Ratio),
+            ("30 ppm", 30e-6, Unit::Ratio),
+            ("30ppm/°C", 30e-6, Unit::PerCelsius),
+            // Other units
+            ("1.2 g", 1.2e-3, Unit::Kilogram),
+            ("250 mW", 0.25, Unit::Watt),
+            ("10 µH", 10e-6, Unit::Henry),
+            ("2000 Hrs", 2000.0 * 3600.0, Unit::Second),
+            ("5 ms", 5e-3, Unit::Second),
+            // Bare numbers
+            ("2", 2.0, Unit::Unitless),
+        ];
+        for &(display_value, expected, unit) in cases {
+            let (quantity, tolerance, conditions) = value(display_value);
+            assert_close(quantity.value, expected, display_value);
+            assert_eq!(quantity.unit, unit, "{:?}", display_value);
+            assert_eq!(tolerance, None, "{:?}", display_value);
+            assert!(conditions.is_empty(), "{:?}", display_value);
+        }
+    }
+
+    #[test]
+    fn parses_conditions() {
+        // The display value, its value, unit and conditions.
+        type Case = (&'static str, f64, Unit, &'static [(f64, Unit)]);
+        let cases: &[Case] = &[
+            (
+                "100 mA @ 120 Hz",
+                0.1,
+                Unit::Ampere,
+                &[(120.0, Unit::Hertz)],
+            ),
+            (
+                "133.4 mA @ 100 kHz",
+                0.1334,
+                Unit::Ampere,
+                &[(1e5, Unit::Hertz)],
+            ),
+            (
+                "137.7 mA @ 50 kHz",
+                0.1377,
+                Unit::Ampere,
+                &[(5e4, Unit::Hertz)],
+            ),
+            (
+                "1.2057Ohm @ 120Hz",
+                1.2057,
+                Unit::Ohm,
+                &[(120.0, Unit::Hertz)],
+            ),
+            (
+                "120.6mOhm @ 120Hz",
+                0.1206,
+                Unit::Ohm,
+                &[(120.0, Unit::Hertz)],
+            ),
+            (
+                "123.46mOhm @ 120Hz 2000 Hrs @ 85°C",
+                0.12346,
+                Unit::Ohm,
+                &[
+                    (120.0, Unit::Hertz),
+                    (7.2e6, Unit::Second),
+                    (85.0, Unit::Celsius),
+                ],
+            ),
+            (
+                "1000 Hrs @ 85°C",
+                3.6e6,
+                Unit::Second,
+                &[(85.0, Unit::Celsius)],
+            ),
+            (
+                "120Hz 2000 Hrs @ 105°C",
+                7.2e6,
+                Unit::Second,
+                &[(120.0, Unit::Hertz), (105.0, Unit::Celsius)],
+            ),
+            (
+                "100Hz 6300 Hrs @ 125°C",
+                6300.0 * 3600.0,
+                Unit::Second,
+                &[(100.0, Unit::Hertz), (125.0, Unit::Celsius)],
+            ),
+        ];
+        for &(display_value, expected, unit, expected_conditions) in cases {
+            let (quantity, _, conditions) = value(display_value);
+            assert_close(quantity.value, expected, display_value);
+            assert_eq!(quantity.unit, unit, "{:?}", display_value);
+            assert_eq!(
+                conditions.len(),
+                expected_conditions.len(),
+                "{:?}",
+                display_value
+            );
+            for (condition, &(expected, unit)) in conditions.iter().zip(expected_conditions) {
+                assert_close(condition.value, expected, display_value);
+                assert_eq!(condition.unit, unit, "{:?}", display_value);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_tolerances() {
+        let cases: &[(&str, f64, Unit)] = &[
+            ("±20%", 0.2, Unit::Ratio),
+            ("±10%", 0.1, Unit::Ratio),
+            ("±5%", 0.05, Unit::Ratio),
+            ("± 1 %", 0.01, Unit::Ratio),
+            ("+/-0.25pF", 0.25e-12, Unit::Farad),
+            ("±30ppm/°C", 30e-6, Unit::PerCelsius),
+        ];
+        for &(display_value, expected, unit) in cases {
+            match parse_display_value(display_value) {
+                Ok(Spec::PlusMinus(quantity)) => {
+                    assert_close(quantity.value, expected, display_value);
+                    assert_eq!(quantity.unit, unit, "{:?}", display_value);
+                }
+                other => panic!("{:?}: expected ±, got {:?}", display_value, other),
+            }
+        }
+
+        let (quantity, tolerance, _) = value("100nF ±10%");
+        assert_close(quantity.value, 100e-9, "100nF ±10%");
+        assert_eq!(tolerance, Some(Tolerance::Relative(0.1)));
+        let (quantity, tolerance, _) = value("10 pF ± 0.5 pF");
+        assert_close(quantity.value, 10e-12, "10 pF ± 0.5 pF");
+        match tolerance {
+            Some(Tolerance::Absolute(tolerance)) => {
+                assert_close(tolerance.value, 0.5e-12, "10 pF ± 0.5 pF");
+                assert_eq!(tolerance.unit, Unit::Farad);
+            }
+            other => panic!("expected an absolute tolerance, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let cases: &[(&str, f64, f64, Unit)] = &[
+            ("-55°C ~ 125°C", -55.0, 125.0, Unit::Celsius),
+            ("-55°C ~ 85°C", -55.0, 85.0, Unit::Celsius),
+            ("-40°C ~ 105°C", -40.0, 105.0, Unit::Celsius),
+            ("-40°C ~ 150°C", -40.0, 150.0, Unit::Celsius),
+            ("-25°C ~ 85°C", -25.0, 85.0, Unit::Celsius),
+            ("-55 ~ 125°C", -55.0, 125.0, Unit::Celsius),
+            ("-40 to 85 °C", -40.0, 85.0, Unit::Celsius),
+            ("1.8V ~ 5.5V", 1.8, 5.5, Unit::Volt),
+            ("-10%, +30%", -0.1, 0.3, Unit::Ratio),
+            ("+80%, -20%", -0.2, 0.8, Unit::Ratio),
+        ];
+        for &(display_value, min, max, unit) in cases {
+            match parse_display_value(display_value) {
+                Ok(Spec::Range {
+                    min: min_quantity,
+                    max: max_quantity,
+                }) => {
+                    assert_close(min_quantity.value, min, display_value);
+                    assert_close(max_quantity.value, max, display_value);
+                    assert_eq!(min_quantity.unit, unit, "{:?}", display_value);
+                    assert_eq!(max_quantity.unit, unit, "{:?}", display_value);
+                }
+                other => panic!("{:?}: expected a range, got {:?}", display_value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_dimensions() {
+        let dimension = |label: &str, length: f64| Dimension {
+            label: (!label.is_empty()).then(|| label.to_string()),
+            length,
+        };
+        let cases: &[(&str, &[Dimension])] = &[
+            (
+                "0.189\" Dia x 0.594\" L (4.80mm x 15.10mm)",
+                &[dimension("Dia", 4.80e-3), dimension("L", 15.10e-3)],
+            ),
+            (
+                "0.375\" Dia x 1.062\" L (9.52mm x 26.97mm)",
+                &[dimension("Dia", 9.52e-3), dimension("L", 26.97e-3)],
+            ),
+            (
+                "0.394\" L x 0.394\" W (10.00mm x 10.00mm)",
+                &[dimension("L", 10e-3), dimension("W", 10e-3)],
+            ),
+            ("0.197\" Dia (5.00mm)", &[dimension("Dia", 5e-3)]),
+            ("0.315\" Dia (8.00mm)", &[dimension("Dia", 8e-3)]),
+            (
+                "0.512\" L x 0.236\" W",
+                &[
+                    dimension("L", 0.512 * 0.0254),
+                    dimension("W", 0.236 * 0.0254),
+                ],
+            ),
+            (
+                "10.00mm x 10.00mm x 5mm",
+                &[
+                    dimension("", 10e-3),
+                    dimension("", 10e-3),
+                    dimension("", 5e-3),
+                ],
+            ),
+        ];
+        for &(display_value, expected) in cases {
+            match parse_display_value(display_value) {
+                Ok(Spec::Dimensions(dimensions)) => {
+                    assert_eq!(dimensions.len(), expected.len(), "{:?}", display_value);
+                    for (dimension, expected) in dimensions.iter().zip(expected) {
+                        assert_eq!(dimension.label, expected.label, "{:?}", display_value);
+                        assert_close(dimension.length, expected.length, display_value);
+                    }
+                }
+                other => panic!("{:?}: expected dimensions, got {:?}", display_value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_quantities() {
+        for display_value in [
+            "",
+            "Radial",
+            "Through Hole",
+            "No SVHC",
+            "Compliant",
+            "PP",
+            "Cut Tape",
+            "8532250070, ",
+            "8532250070|8532250070",
+            "0805",
+            "0402",
+            "100 nF ~ 5 V",
+            "5 furlongs",
+            "0.189\" Dia x Tall",
+            "1 min",
+        ] {
+            assert!(
+                parse_display_value(display_value).is_err(),
+                "{:?} parsed as {:?}",
+                display_value,
+                parse_display_value(display_value)
+            );
+        }
+    }
+
+    #[test]
+    fn nominal_values() {
+        assert_eq!(parse_display_value("±20%").unwrap().nominal(), Some(0.2));
+        assert_eq!(parse_display_value("6.3 V").unwrap().nominal(), Some(6.3));
+        assert_eq!(
+            parse_display_value("-55°C ~ 125°C").unwrap().nominal(),
+            None
+        );
+    }
+}